@@ -0,0 +1,142 @@
+use crate::records::{Record, RecordProblem};
+use crate::safety_policy::{Offence, SafetyPolicy};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ReportFormat {
+    Table,
+    Json,
+}
+
+impl ReportFormat {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "table" => Ok(ReportFormat::Table),
+            "json" => Ok(ReportFormat::Json),
+            _ => Err(format!("unknown report format '{}'", s)),
+        }
+    }
+}
+
+/// Why a single report is or isn't safe. `offence` is the first problem in the
+/// original report and `removed` is the level the dampener dropped to fix it.
+/// Reports that could not be parsed only carry their `problem`.
+pub struct Diagnosis {
+    pub line: usize,
+    pub levels: Vec<i32>,
+    pub problem: Option<RecordProblem>,
+    pub offence: Option<Offence>,
+    pub removed: Option<usize>,
+}
+
+impl Diagnosis {
+    pub fn status(&self) -> &'static str {
+        if self.problem.is_some() {
+            return "unsafe";
+        }
+        match (self.offence, self.removed) {
+            (None, _) => "safe",
+            (Some(_), Some(_)) => "dampened",
+            (Some(_), None) => "unsafe",
+        }
+    }
+}
+
+pub fn diagnose_records(data: &[Record], policy: &SafetyPolicy) -> Vec<Diagnosis> {
+    data.iter()
+        .map(|record| {
+            let (offence, removed) = match record.problem {
+                Some(_) => (None, None),
+                None => {
+                    let offence = policy.check_record(&record.levels).err();
+                    let removed = offence.and_then(|_| find_dampened_index(&record.levels, policy));
+                    (offence, removed)
+                }
+            };
+            Diagnosis {
+                line: record.line,
+                levels: record.levels.clone(),
+                problem: record.problem.clone(),
+                offence,
+                removed,
+            }
+        })
+        .collect()
+}
+
+fn find_dampened_index(record: &[i32], policy: &SafetyPolicy) -> Option<usize> {
+    (0..record.len()).find(|&i| {
+        let mut new_record = record.to_vec();
+        new_record.remove(i);
+        policy.check_record(&new_record).is_ok()
+    })
+}
+
+pub fn print_report(diagnoses: &[Diagnosis], format: ReportFormat) {
+    match format {
+        ReportFormat::Table => print_table(diagnoses),
+        ReportFormat::Json => print_json(diagnoses),
+    }
+}
+
+fn print_table(diagnoses: &[Diagnosis]) {
+    println!(
+        "{:>5}  {:<8}  {:>5}  {:<20}  {:>7}  levels",
+        "line", "status", "index", "reason", "removed"
+    );
+    for d in diagnoses {
+        let (index, reason) = match (&d.problem, d.offence) {
+            (Some(problem), _) => ("-".to_string(), problem.to_string()),
+            (None, Some(offence)) => (offence.index.to_string(), offence.reason.to_string()),
+            (None, None) => ("-".to_string(), "-".to_string()),
+        };
+        let removed = d.removed.map_or("-".to_string(), |i| i.to_string());
+        println!(
+            "{:>5}  {:<8}  {:>5}  {:<20}  {:>7}  {}",
+            d.line,
+            d.status(),
+            index,
+            reason,
+            removed,
+            join_levels(&d.levels, " ")
+        );
+    }
+}
+
+fn print_json(diagnoses: &[Diagnosis]) {
+    println!("[");
+    for (i, d) in diagnoses.iter().enumerate() {
+        let (index, reason) = match (&d.problem, d.offence) {
+            (Some(problem), _) => ("null".to_string(), json_string(&problem.to_string())),
+            (None, Some(offence)) => (
+                offence.index.to_string(),
+                json_string(&offence.reason.to_string()),
+            ),
+            (None, None) => ("null".to_string(), "null".to_string()),
+        };
+        let removed = d.removed.map_or("null".to_string(), |i| i.to_string());
+        let separator = if i + 1 < diagnoses.len() { "," } else { "" };
+        println!(
+            "  {{\"line\": {}, \"levels\": [{}], \"status\": \"{}\", \"index\": {}, \"reason\": {}, \"removed\": {}}}{}",
+            d.line,
+            join_levels(&d.levels, ", "),
+            d.status(),
+            index,
+            reason,
+            removed,
+            separator
+        );
+    }
+    println!("]");
+}
+
+fn join_levels(levels: &[i32], separator: &str) -> String {
+    levels
+        .iter()
+        .map(|x| x.to_string())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+fn json_string(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
@@ -1,87 +1,167 @@
+mod diagnostics;
+mod records;
+mod safety_policy;
+
+use diagnostics::ReportFormat;
+use records::{InvalidRecordPolicy, Record, SkippedLine};
+use safety_policy::SafetyPolicy;
+use std::env::args;
 use std::fs::File;
 use std::io::{self, BufRead, Write};
 
-trait Betweeen {
-    fn between(&self, a: i32, b: i32) -> bool;
-}
-impl Betweeen for i32 {
-    fn between(&self, a: i32, b: i32) -> bool {
-        return *self >= a && *self <= b;
-    }
+struct Options {
+    file_name: Option<String>,
+    policy: SafetyPolicy,
+    report: Option<ReportFormat>,
+    on_invalid: InvalidRecordPolicy,
 }
 
 fn main() {
-    print!("Enter file name: ");
-    io::stdout().flush().expect("Failed to flush stdout");
+    let args: Vec<String> = args().collect();
+    let options = parse_args(&args[1..]).unwrap_or_else(|err| panic!("Invalid arguments: {}", err));
+
+    let file_name = match options.file_name {
+        Some(file_name) => file_name,
+        None => {
+            print!("Enter file name: ");
+            io::stdout().flush().expect("Failed to flush stdout");
+
+            let mut file_name = String::new();
+            io::stdin()
+                .read_line(&mut file_name)
+                .expect("Failed to read line");
+            file_name.trim().to_string()
+        }
+    };
 
-    let mut file_name = String::new();
-    io::stdin()
-        .read_line(&mut file_name)
-        .expect("Failed to read line");
-    file_name = file_name.trim().to_string();
+    let (data, skipped) =
+        read_from_file(&file_name, options.on_invalid).expect("Error reading from file");
+    records::print_skipped_summary(&skipped);
 
-    let data = read_from_file(&file_name).expect("Error reading from file");
+    if let Some(format) = options.report {
+        let diagnoses = diagnostics::diagnose_records(&data, &options.policy);
+        diagnostics::print_report(&diagnoses, format);
+        if format == ReportFormat::Json {
+            return;
+        }
+    }
 
-    let count = classify_safety_records(&data);
+    let count = classify_safety_records(&data, &options.policy);
     println!("Num Safe Records: {}", count);
 
-    let count = classify_with_problem_dampener(&data);
+    let count = classify_with_problem_dampener(&data, &options.policy);
     println!("Num Safe Records with Dampener: {}", count);
 }
 
-fn read_from_file(file_name: &str) -> io::Result<Vec<Vec<i32>>> {
-    let file = File::open(file_name)?;
-    let lines = io::BufReader::new(file).lines();
-
-    let mut data: Vec<Vec<i32>> = Vec::new();
-
-    for line in lines {
-        let nums: Vec<i32> = line
-            .unwrap()
-            .split_whitespace()
-            .map(|x| x.parse().unwrap())
-            .collect();
-        data.push(nums);
+/// Parses `[file] [--config FILE] [--min-step N] [--max-step N] [--allow-flat]
+/// [--direction increasing|decreasing|either] [--report table|json]
+/// [--on-invalid skip|unsafe|abort]`. Options are applied in order,
+/// so flags given after `--config` override the values from the file.
+fn parse_args(args: &[String]) -> Result<Options, String> {
+    let mut file_name = None;
+    let mut policy = SafetyPolicy::default();
+    let mut report = None;
+    let mut on_invalid = InvalidRecordPolicy::Skip;
+
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let Some(key) = arg.strip_prefix("--") else {
+            if file_name.is_some() {
+                return Err(format!("unexpected argument '{}'", arg));
+            }
+            file_name = Some(arg.clone());
+            continue;
+        };
+        match key {
+            "allow-flat" => policy.allow_flat = true,
+            "config" => {
+                let path = iter.next().ok_or("--config needs a file name")?;
+                policy.load_config_file(path)?;
+            }
+            "report" => {
+                let format = iter.next().ok_or("--report needs a format")?;
+                report = Some(ReportFormat::parse(format)?);
+            }
+            "on-invalid" => {
+                let value = iter.next().ok_or("--on-invalid needs a policy")?;
+                on_invalid = InvalidRecordPolicy::parse(value)?;
+            }
+            _ => {
+                let value = iter
+                    .next()
+                    .ok_or_else(|| format!("--{} needs a value", key))?;
+                policy.set_option(key, value)?;
+            }
+        }
     }
-
-    return Ok(data);
+    policy.validate()?;
+
+    Ok(Options {
+        file_name,
+        policy,
+        report,
+        on_invalid,
+    })
 }
 
-fn check_record(record: &Vec<i32>) -> bool {
-    let diffs: Vec<i32> = record
-        .windows(2)
-        .map(|window| window[0] - window[1])
-        .collect();
+/// Reads one report per line. Empty, single level and malformed lines are
+/// skipped with a warning, kept as unsafe reports, or abort the read
+/// depending on `on_invalid`.
+fn read_from_file(
+    file_name: &str,
+    on_invalid: InvalidRecordPolicy,
+) -> io::Result<(Vec<Record>, Vec<SkippedLine>)> {
+    let file = File::open(file_name)?;
+    let lines = io::BufReader::new(file).lines();
 
-    if diffs.iter().all(|&x| x.abs().between(1, 3))
-        && (diffs.iter().all(|&x| x > 0) || diffs.iter().all(|&x| x < 0))
-    {
-        return true;
+    let mut data: Vec<Record> = Vec::new();
+    let mut skipped: Vec<SkippedLine> = Vec::new();
+
+    for (i, line) in lines.enumerate() {
+        let record = Record::parse(i + 1, &line?);
+        match (&record.problem, on_invalid) {
+            (None, _) | (Some(_), InvalidRecordPolicy::Unsafe) => data.push(record),
+            (Some(problem), InvalidRecordPolicy::Skip) => {
+                eprintln!("warning: skipping line {}: {}", record.line, problem);
+                skipped.push(SkippedLine {
+                    line: record.line,
+                    problem: problem.clone(),
+                });
+            }
+            (Some(problem), InvalidRecordPolicy::Abort) => {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("line {}: {}", record.line, problem),
+                ));
+            }
+        }
     }
-    return false;
+
+    Ok((data, skipped))
 }
 
-fn classify_safety_records(data: &Vec<Vec<i32>>) -> i32 {
+fn classify_safety_records(data: &[Record], policy: &SafetyPolicy) -> i32 {
     let mut count = 0;
     for record in data {
-        if check_record(&record) {
+        if record.problem.is_none() && policy.check_record(&record.levels).is_ok() {
             count += 1;
         }
     }
 
-    return count;
+    count
 }
 
-fn classify_with_problem_dampener(data: &Vec<Vec<i32>>) -> i32 {
+fn classify_with_problem_dampener(data: &[Record], policy: &SafetyPolicy) -> i32 {
     let mut count = 0;
-    for record in data {
-        if check_record(&record) {
+    for record in data.iter().filter(|record| record.problem.is_none()) {
+        let levels = &record.levels;
+        if policy.check_record(levels).is_ok() {
             count += 1;
         } else {
-            for i in 0..record.len() {
-                let mut new_record = record.clone();
+            for i in 0..levels.len() {
+                let mut new_record = levels.clone();
                 new_record.remove(i);
-                if check_record(&new_record) {
+                if policy.check_record(&new_record).is_ok() {
                     count += 1;
                     break;
                 }
@@ -89,5 +169,5 @@ fn classify_with_problem_dampener(data: &Vec<Vec<i32>>) -> i32 {
         }
     }
 
-    return count;
+    count
 }
@@ -0,0 +1,95 @@
+use std::fmt;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordProblem {
+    Empty,
+    SingleLevel,
+    Malformed(String),
+}
+
+impl fmt::Display for RecordProblem {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            RecordProblem::Empty => write!(f, "empty report"),
+            RecordProblem::SingleLevel => write!(f, "single level"),
+            RecordProblem::Malformed(token) => write!(f, "malformed level '{}'", token),
+        }
+    }
+}
+
+/// What to do with a line that isn't a usable report.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InvalidRecordPolicy {
+    Skip,
+    Unsafe,
+    Abort,
+}
+
+impl InvalidRecordPolicy {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(InvalidRecordPolicy::Skip),
+            "unsafe" => Ok(InvalidRecordPolicy::Unsafe),
+            "abort" => Ok(InvalidRecordPolicy::Abort),
+            _ => Err(format!("unknown invalid report policy '{}'", s)),
+        }
+    }
+}
+
+/// A report together with the line it came from. Reports kept under
+/// `InvalidRecordPolicy::Unsafe` carry their `problem` and are never safe.
+#[derive(Clone, Debug)]
+pub struct Record {
+    pub line: usize,
+    pub levels: Vec<i32>,
+    pub problem: Option<RecordProblem>,
+}
+
+impl Record {
+    pub fn parse(line_no: usize, line: &str) -> Self {
+        let mut levels = Vec::new();
+        for token in line.split_whitespace() {
+            match token.parse::<i32>() {
+                Ok(level) => levels.push(level),
+                Err(_) => {
+                    return Record {
+                        line: line_no,
+                        levels: Vec::new(),
+                        problem: Some(RecordProblem::Malformed(token.to_string())),
+                    }
+                }
+            }
+        }
+        let problem = match levels.len() {
+            0 => Some(RecordProblem::Empty),
+            1 => Some(RecordProblem::SingleLevel),
+            _ => None,
+        };
+        Record {
+            line: line_no,
+            levels,
+            problem,
+        }
+    }
+}
+
+pub struct SkippedLine {
+    pub line: usize,
+    pub problem: RecordProblem,
+}
+
+pub fn print_skipped_summary(skipped: &[SkippedLine]) {
+    if skipped.is_empty() {
+        return;
+    }
+    let count = |f: fn(&RecordProblem) -> bool| skipped.iter().filter(|s| f(&s.problem)).count();
+    eprintln!(
+        "Skipped {} lines: {} empty, {} single level, {} malformed",
+        skipped.len(),
+        count(|p| *p == RecordProblem::Empty),
+        count(|p| *p == RecordProblem::SingleLevel),
+        count(|p| matches!(p, RecordProblem::Malformed(_)))
+    );
+    let lines: Vec<String> = skipped.iter().map(|s| s.line.to_string()).collect();
+    eprintln!("Skipped line numbers: {}", lines.join(", "));
+}
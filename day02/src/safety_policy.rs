@@ -0,0 +1,183 @@
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Direction {
+    Increasing,
+    Decreasing,
+    Either,
+}
+
+impl Direction {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s.to_lowercase().as_str() {
+            "increasing" | "inc" => Ok(Direction::Increasing),
+            "decreasing" | "dec" => Ok(Direction::Decreasing),
+            "either" | "any" => Ok(Direction::Either),
+            _ => Err(format!("unknown direction '{}'", s)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Violation {
+    StepTooSmall,
+    StepTooBig,
+    FlatStep,
+    DirectionChange,
+    WrongDirection,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let reason = match self {
+            Violation::StepTooSmall => "step too small",
+            Violation::StepTooBig => "step too big",
+            Violation::FlatStep => "flat step",
+            Violation::DirectionChange => "direction change",
+            Violation::WrongDirection => "wrong direction",
+        };
+        write!(f, "{}", reason)
+    }
+}
+
+/// The first place a report breaks the policy. `index` is the level that
+/// completes the offending step.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Offence {
+    pub index: usize,
+    pub reason: Violation,
+}
+
+/// Rules a report has to follow to be considered safe. The defaults match the
+/// puzzle: every step changes the level by 1 to 3 and the report is strictly
+/// increasing or strictly decreasing.
+#[derive(Clone, Debug)]
+pub struct SafetyPolicy {
+    pub min_step: i32,
+    pub max_step: i32,
+    pub allow_flat: bool,
+    pub direction: Direction,
+}
+
+impl Default for SafetyPolicy {
+    fn default() -> Self {
+        SafetyPolicy {
+            min_step: 1,
+            max_step: 3,
+            allow_flat: false,
+            direction: Direction::Either,
+        }
+    }
+}
+
+impl SafetyPolicy {
+    /// Applies a single `key value` setting. The keys are shared between the
+    /// command line flags (without the leading `--`) and the config file.
+    pub fn set_option(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "min-step" => self.min_step = parse_step(key, value)?,
+            "max-step" => self.max_step = parse_step(key, value)?,
+            "allow-flat" => {
+                self.allow_flat = value
+                    .parse()
+                    .map_err(|_| format!("invalid value '{}' for {}", value, key))?
+            }
+            "direction" => self.direction = Direction::parse(value)?,
+            _ => return Err(format!("unknown option '{}'", key)),
+        }
+        Ok(())
+    }
+
+    /// Reads `key = value` lines from a config file. Blank lines and lines
+    /// starting with `#` are ignored.
+    pub fn load_config_file(&mut self, file_name: &str) -> Result<(), String> {
+        let file = File::open(file_name)
+            .map_err(|err| format!("could not open config file '{}': {}", file_name, err))?;
+
+        for (line_no, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|err| err.to_string())?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line
+                .split_once('=')
+                .ok_or_else(|| format!("{}:{}: expected 'key = value'", file_name, line_no + 1))?;
+            self.set_option(key.trim(), value.trim())
+                .map_err(|err| format!("{}:{}: {}", file_name, line_no + 1, err))?;
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.min_step > self.max_step {
+            return Err(format!(
+                "min-step ({}) is larger than max-step ({})",
+                self.min_step, self.max_step
+            ));
+        }
+        Ok(())
+    }
+
+    /// Walks the steps of a report in order and returns the first one that
+    /// breaks the policy. Under `Direction::Either` the first non-flat step
+    /// decides which way the rest of the report has to go.
+    pub fn check_record(&self, record: &[i32]) -> Result<(), Offence> {
+        let mut heading: Option<bool> = match self.direction {
+            Direction::Increasing => Some(true),
+            Direction::Decreasing => Some(false),
+            Direction::Either => None,
+        };
+
+        for (i, window) in record.windows(2).enumerate() {
+            let step = window[1] - window[0];
+            let index = i + 1;
+            if let Some(reason) = self.check_step(step) {
+                return Err(Offence { index, reason });
+            }
+            if step == 0 {
+                continue;
+            }
+            match heading {
+                Some(up) if up != (step > 0) => {
+                    let reason = match self.direction {
+                        Direction::Either => Violation::DirectionChange,
+                        _ => Violation::WrongDirection,
+                    };
+                    return Err(Offence { index, reason });
+                }
+                Some(_) => {}
+                None => heading = Some(step > 0),
+            }
+        }
+        Ok(())
+    }
+
+    /// A flat step is only judged by `allow_flat`; any other step has to
+    /// change the level by `min_step..=max_step`.
+    fn check_step(&self, step: i32) -> Option<Violation> {
+        if step == 0 {
+            return if self.allow_flat {
+                None
+            } else {
+                Some(Violation::FlatStep)
+            };
+        }
+        if step.abs() < self.min_step {
+            Some(Violation::StepTooSmall)
+        } else if step.abs() > self.max_step {
+            Some(Violation::StepTooBig)
+        } else {
+            None
+        }
+    }
+}
+
+fn parse_step(key: &str, value: &str) -> Result<i32, String> {
+    match value.parse::<i32>() {
+        Ok(step) if step >= 0 => Ok(step),
+        _ => Err(format!("invalid value '{}' for {}", value, key)),
+    }
+}
@@ -0,0 +1,152 @@
+use crate::interpreter::Machine;
+
+/// Returned by an instruction whose arithmetic doesn't fit in an `i64`.
+#[derive(Debug)]
+pub struct Overflow;
+
+/// What an instruction does to the machine when it runs. The return value is
+/// the number the instruction computed, whether or not it was counted.
+pub type Semantics = fn(&mut Machine, &[i64]) -> Result<Option<i64>, Overflow>;
+
+/// AoC only accepts operands of 1-3 digits.
+pub const DEFAULT_MAX_DIGITS: usize = 3;
+
+/// One instruction the scanner can recognize, written as `name(a,b,...)` with
+/// exactly `arity` decimal operands. `max_digits` limits the length of each
+/// operand; `None` accepts any length that fits in an `i64`.
+pub struct InstructionDef {
+    pub name: &'static str,
+    pub arity: usize,
+    pub max_digits: Option<usize>,
+    pub execute: Semantics,
+}
+
+pub struct InstructionSet {
+    defs: Vec<InstructionDef>,
+}
+
+impl InstructionSet {
+    pub fn new() -> Self {
+        InstructionSet { defs: Vec::new() }
+    }
+
+    pub fn register(
+        mut self,
+        name: &'static str,
+        arity: usize,
+        max_digits: Option<usize>,
+        execute: Semantics,
+    ) -> Self {
+        self.defs.push(InstructionDef {
+            name,
+            arity,
+            max_digits,
+            execute,
+        });
+        self
+    }
+
+    /// Replaces the operand length limit of every instruction that takes
+    /// operands.
+    pub fn with_max_digits(mut self, max_digits: Option<usize>) -> Self {
+        for def in self.defs.iter_mut().filter(|def| def.arity > 0) {
+            def.max_digits = max_digits;
+        }
+        self
+    }
+
+    pub fn defs(&self) -> &[InstructionDef] {
+        &self.defs
+    }
+
+    pub fn get(&self, idx: usize) -> &InstructionDef {
+        &self.defs[idx]
+    }
+
+    /// Part 1: only `mul` is recognized, so every multiplication counts.
+    pub fn part1() -> Self {
+        InstructionSet::new().register("mul", 2, Some(DEFAULT_MAX_DIGITS), mul)
+    }
+
+    /// Part 2: `do()` and `don't()` switch later multiplications on and off.
+    pub fn part2() -> Self {
+        InstructionSet::part1()
+            .register("do", 0, None, enable)
+            .register("don't", 0, None, disable)
+    }
+
+    /// Part 2 plus `add` and `sub`.
+    pub fn extended() -> Self {
+        InstructionSet::new()
+            .register("mul", 2, Some(DEFAULT_MAX_DIGITS), mul)
+            .register("add", 2, Some(DEFAULT_MAX_DIGITS), add)
+            .register("sub", 2, Some(DEFAULT_MAX_DIGITS), sub)
+            .register("do", 0, None, enable)
+            .register("don't", 0, None, disable)
+    }
+
+    /// Like `extended`, but toggles nest: each `don't()` has to be undone by
+    /// its own `do()` before instructions run again.
+    pub fn nested() -> Self {
+        InstructionSet::new()
+            .register("mul", 2, Some(DEFAULT_MAX_DIGITS), mul)
+            .register("add", 2, Some(DEFAULT_MAX_DIGITS), add)
+            .register("sub", 2, Some(DEFAULT_MAX_DIGITS), sub)
+            .register("do", 0, None, pop_disable)
+            .register("don't", 0, None, push_disable)
+    }
+
+    pub fn by_name(name: &str) -> Result<Self, String> {
+        match name {
+            "part1" => Ok(InstructionSet::part1()),
+            "part2" => Ok(InstructionSet::part2()),
+            "extended" => Ok(InstructionSet::extended()),
+            "nested" => Ok(InstructionSet::nested()),
+            _ => Err(format!("unknown instruction set '{}'", name)),
+        }
+    }
+}
+
+fn mul(m: &mut Machine, args: &[i64]) -> Result<Option<i64>, Overflow> {
+    let value = args[0].checked_mul(args[1]).ok_or(Overflow)?;
+    if m.enabled() {
+        m.total = m.total.checked_add(value).ok_or(Overflow)?;
+    }
+    Ok(Some(value))
+}
+
+fn add(m: &mut Machine, args: &[i64]) -> Result<Option<i64>, Overflow> {
+    let value = args[0].checked_add(args[1]).ok_or(Overflow)?;
+    if m.enabled() {
+        m.total = m.total.checked_add(value).ok_or(Overflow)?;
+    }
+    Ok(Some(value))
+}
+
+fn sub(m: &mut Machine, args: &[i64]) -> Result<Option<i64>, Overflow> {
+    let value = args[0].checked_sub(args[1]).ok_or(Overflow)?;
+    if m.enabled() {
+        m.total = m.total.checked_add(value).ok_or(Overflow)?;
+    }
+    Ok(Some(value))
+}
+
+fn enable(m: &mut Machine, _: &[i64]) -> Result<Option<i64>, Overflow> {
+    m.disabled_depth = 0;
+    Ok(None)
+}
+
+fn disable(m: &mut Machine, _: &[i64]) -> Result<Option<i64>, Overflow> {
+    m.disabled_depth = 1;
+    Ok(None)
+}
+
+fn pop_disable(m: &mut Machine, _: &[i64]) -> Result<Option<i64>, Overflow> {
+    m.disabled_depth = m.disabled_depth.saturating_sub(1);
+    Ok(None)
+}
+
+fn push_disable(m: &mut Machine, _: &[i64]) -> Result<Option<i64>, Overflow> {
+    m.disabled_depth += 1;
+    Ok(None)
+}
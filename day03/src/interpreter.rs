@@ -0,0 +1,93 @@
+use crate::instruction_set::InstructionSet;
+use crate::scanner::{Call, Event, Rejection, Scanner};
+use std::io::{self, Read};
+
+/// State shared by every instruction of a program.
+#[derive(Clone, Debug, Default)]
+pub struct Machine {
+    pub total: i64,
+    pub disabled_depth: u32,
+}
+
+impl Machine {
+    pub fn enabled(&self) -> bool {
+        self.disabled_depth == 0
+    }
+}
+
+/// What the interpreter did with one scanner event.
+pub enum Step<'a> {
+    Executed {
+        call: &'a Call,
+        enabled: bool,
+        value: Option<i64>,
+    },
+    Rejected(&'a Rejection),
+}
+
+/// A program that can be fed in several pieces. The machine state and any
+/// half-read instruction carry over between calls to `feed`, so feeding
+/// several inputs gives the same result as feeding them concatenated.
+pub struct Session<'a> {
+    set: &'a InstructionSet,
+    scanner: Scanner<'a>,
+    machine: Machine,
+}
+
+impl<'a> Session<'a> {
+    pub fn new(set: &'a InstructionSet) -> Self {
+        Session {
+            set,
+            scanner: Scanner::new(set),
+            machine: Machine::default(),
+        }
+    }
+
+    pub fn machine(&self) -> &Machine {
+        &self.machine
+    }
+
+    /// Executes the instructions in `reader`, reporting every executed
+    /// instruction, with whether the machine was enabled when it ran and the
+    /// value it computed, and every near miss to `on_step`. Arithmetic
+    /// overflow stops the run with an error.
+    pub fn feed<R: Read>(&mut self, reader: R, mut on_step: impl FnMut(Step)) -> io::Result<()> {
+        let set = self.set;
+        let machine = &mut self.machine;
+        self.scanner.feed_reader(reader, |event| {
+            match event {
+                Event::Call(call) => {
+                    let enabled = machine.enabled();
+                    let value = (set.get(call.def).execute)(machine, &call.args).map_err(|_| {
+                        io::Error::new(
+                            io::ErrorKind::InvalidData,
+                            format!(
+                                "arithmetic overflow in '{}' at {}:{}",
+                                call.text, call.start.line, call.start.column
+                            ),
+                        )
+                    })?;
+                    on_step(Step::Executed {
+                        call: &call,
+                        enabled,
+                        value,
+                    });
+                }
+                Event::Rejected(rejection) => on_step(Step::Rejected(&rejection)),
+            }
+            Ok(())
+        })
+    }
+}
+
+/// Runs every reader in `readers` as one program and returns the final state.
+pub fn run<R: Read>(
+    set: &InstructionSet,
+    readers: impl IntoIterator<Item = R>,
+) -> io::Result<Machine> {
+    let mut session = Session::new(set);
+    for reader in readers {
+        session.feed(reader, |_| {})?;
+    }
+    Ok(session.machine().clone())
+}
@@ -1,51 +1,141 @@
-use regex::Regex;
-use std::fs;
-use std::io::{self, Write};
+mod instruction_set;
+mod interpreter;
+mod scanner;
+mod trace;
+
+use instruction_set::{InstructionSet, DEFAULT_MAX_DIGITS};
+use interpreter::{Session, Step};
+use scanner::Rejection;
+use std::env::args;
+use std::fs::File;
+use std::io::{self, Read, Write};
+use trace::TraceEntry;
 
 fn main() {
-    print!("Enter file name: ");
-    io::stdout().flush().expect("Failed to flush stdout");
+    let args: Vec<String> = args().collect();
+    let mut file_names: Vec<String> = Vec::new();
+    let mut set_name: Option<String> = None;
+    let mut show_trace = false;
+    let mut annotate = false;
+    let mut near_misses = false;
+    let mut max_digits = Some(DEFAULT_MAX_DIGITS);
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--set" => set_name = Some(iter.next().expect("--set needs a name").clone()),
+            "--trace" => show_trace = true,
+            "--annotate" => annotate = true,
+            "--near-misses" => near_misses = true,
+            "--max-digits" => {
+                // `none` lifts the limit entirely.
+                let value = iter.next().expect("--max-digits needs a value");
+                max_digits = match value.as_str() {
+                    "none" => None,
+                    _ => Some(value.parse().expect("Invalid --max-digits")),
+                };
+            }
+            _ => file_names.push(arg.clone()),
+        }
+    }
+
+    // Several files are run as one program, in the order given.
+    if file_names.is_empty() {
+        print!("Enter file name: ");
+        io::stdout().flush().expect("Failed to flush stdout");
 
-    let mut file_name = String::new();
-    io::stdin()
-        .read_line(&mut file_name)
-        .expect("Failed to read line");
-    file_name = file_name.trim().to_string();
+        let mut file_name = String::new();
+        io::stdin()
+            .read_line(&mut file_name)
+            .expect("Failed to read line");
+        file_names.push(file_name.trim().to_string());
+    }
+
+    if show_trace || annotate || near_misses {
+        // Tracing is most useful with the conditionals, so default to part 2.
+        let set_name = set_name.unwrap_or("part2".to_string());
+        let set = load_set(&set_name, max_digits);
+        let mut entries: Vec<TraceEntry> = Vec::new();
+        let mut rejections: Vec<Rejection> = Vec::new();
+        let mut session = Session::new(&set);
+        for file in open_files(&file_names) {
+            session
+                .feed(file, |step| match step {
+                    Step::Executed {
+                        call,
+                        enabled,
+                        value,
+                    } => entries.push(TraceEntry::new(call, enabled, value)),
+                    Step::Rejected(rejection) => rejections.push(rejection.clone()),
+                })
+                .expect("Error running program");
+        }
+
+        if show_trace {
+            trace::print_trace(&entries);
+        }
+        if near_misses {
+            trace::print_near_misses(&rejections);
+        }
+        if annotate {
+            let files = open_files(&file_names);
+            let reader = files
+                .into_iter()
+                .fold(Box::new(io::empty()) as Box<dyn Read>, |acc, file| {
+                    Box::new(acc.chain(file))
+                });
+            trace::print_annotated(reader, &entries)
+                .expect("Something went wrong reading the file");
+        }
+        println!("Total ({}): {}", set_name, session.machine().total);
+        return;
+    }
 
-    let data = fs::read_to_string(file_name).expect("Something went wrong reading the file");
+    if let Some(set_name) = set_name {
+        let set = load_set(&set_name, max_digits);
+        let machine =
+            interpreter::run(&set, open_files(&file_names)).expect("Error running program");
+        println!("Total ({}): {}", set_name, machine.total);
+        return;
+    }
 
-    let total = parse_multiplication(&data);
+    let total =
+        parse_multiplication(open_files(&file_names), max_digits).expect("Error running program");
     println!("Total: {}", total);
 
-    let total = parse_mult_with_conditions(&data);
+    let total = parse_mult_with_conditions(open_files(&file_names), max_digits)
+        .expect("Error running program");
     println!("Total with conditionals: {}", total);
 }
 
-fn parse_multiplication(data: &str) -> i32 {
-    let mut total: i32 = 0;
-    let re = Regex::new(r"mul\((\d+),(\d+)\)").expect("Invalid regex");
-    for cap in re.captures_iter(data) {
-        let (a, b): (i32, i32) = (cap[1].parse().unwrap(), cap[2].parse().unwrap());
-        total += a * b;
-    }
-    return total;
+fn open_files(file_names: &[String]) -> Vec<File> {
+    file_names
+        .iter()
+        .map(|file_name| File::open(file_name).expect("Something went wrong reading the file"))
+        .collect()
 }
 
-fn parse_mult_with_conditions(data: &str) -> i32 {
-    let mut enable: bool = true;
-    let mut total: i32 = 0;
-    let re = Regex::new(r"mul\((\d+),(\d+)\)|do\(\)|don't\(\)").expect("Invalid regex");
-    for cap in re.captures_iter(data) {
-        match &cap[0] {
-            "do()" => enable = true,
-            "don't()" => enable = false,
-            _ => {
-                if cap.get(1).is_some() && cap.get(2).is_some() {
-                    let (a, b): (i32, i32) = (cap[1].parse().unwrap(), cap[2].parse().unwrap());
-                    total += if enable { a * b } else { 0 };
-                }
-            }
-        }
-    }
-    return total;
+fn load_set(set_name: &str, max_digits: Option<usize>) -> InstructionSet {
+    InstructionSet::by_name(set_name)
+        .unwrap_or_else(|err| panic!("{}", err))
+        .with_max_digits(max_digits)
+}
+
+fn parse_multiplication<R: Read>(
+    readers: impl IntoIterator<Item = R>,
+    max_digits: Option<usize>,
+) -> io::Result<i64> {
+    let set = InstructionSet::part1().with_max_digits(max_digits);
+    let machine = interpreter::run(&set, readers)?;
+    Ok(machine.total)
+}
+
+/// The `enable` flag and any instruction split between two readers carry
+/// over, so the readers behave like one concatenated input.
+fn parse_mult_with_conditions<R: Read>(
+    readers: impl IntoIterator<Item = R>,
+    max_digits: Option<usize>,
+) -> io::Result<i64> {
+    let set = InstructionSet::part2().with_max_digits(max_digits);
+    let machine = interpreter::run(&set, readers)?;
+    Ok(machine.total)
 }
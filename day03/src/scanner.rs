@@ -0,0 +1,272 @@
+use crate::instruction_set::{InstructionDef, InstructionSet};
+use std::io::{self, Read};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Where a byte sits in the input. `line` and `column` are 1-based and count
+/// bytes, not characters.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Position {
+    pub fn start() -> Self {
+        Position {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+
+    fn advance(&mut self, byte: u8) {
+        self.offset += 1;
+        if byte == b'\n' {
+            self.line += 1;
+            self.column = 1;
+        } else {
+            self.column += 1;
+        }
+    }
+}
+
+/// A recognized instruction: `def` indexes into the scanner's instruction set
+/// and `text` is the instruction exactly as it appeared at `start`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Call {
+    pub def: usize,
+    pub args: Vec<i64>,
+    pub start: Position,
+    pub text: String,
+}
+
+/// Text that spelled out a known instruction name but then broke its syntax,
+/// e.g. `mul[3,7]` or `mul(1234,5)`. `text` runs up to the offending byte.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rejection {
+    pub start: Position,
+    pub text: String,
+    pub reason: String,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    Call(Call),
+    Rejected(Rejection),
+}
+
+/// `Fail` records how far into the candidate the instruction got before it
+/// stopped matching, so near misses are only reported for the best match.
+enum Match {
+    Partial,
+    Complete(Call),
+    Fail(usize, Option<String>),
+}
+
+/// Streaming tokenizer for corrupted memory. Bytes are fed in any chunk size
+/// and the scanner keeps the start of a possible instruction in `pending`, so
+/// an instruction split across two chunks is still recognized.
+pub struct Scanner<'a> {
+    set: &'a InstructionSet,
+    pending: Vec<u8>,
+    pending_start: Position,
+}
+
+impl<'a> Scanner<'a> {
+    pub fn new(set: &'a InstructionSet) -> Self {
+        Scanner {
+            set,
+            pending: Vec::new(),
+            pending_start: Position::start(),
+        }
+    }
+
+    pub fn feed(&mut self, chunk: &[u8], out: &mut Vec<Event>) {
+        for &byte in chunk {
+            self.push(byte, out);
+        }
+    }
+
+    /// Reads `reader` in fixed size chunks and hands every instruction and
+    /// near miss to `f` in the order it appears. An error from `f` stops the
+    /// scan. An instruction cut off at the end of `reader` stays pending, so
+    /// it can still be completed by the next reader.
+    pub fn feed_reader<R: Read>(
+        &mut self,
+        mut reader: R,
+        mut f: impl FnMut(Event) -> io::Result<()>,
+    ) -> io::Result<()> {
+        let mut buf = vec![0u8; CHUNK_SIZE];
+        let mut out = Vec::new();
+        loop {
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            self.feed(&buf[..n], &mut out);
+            out.drain(..).try_for_each(&mut f)?;
+        }
+        Ok(())
+    }
+
+    /// Adds one byte to the pending candidate. When the candidate can no
+    /// longer become an instruction we drop its first byte and retry from the
+    /// next one, which gives the same leftmost matches as a regex scan.
+    fn push(&mut self, byte: u8, out: &mut Vec<Event>) {
+        self.pending.push(byte);
+        while !self.pending.is_empty() {
+            match self.classify(&self.pending) {
+                Match::Partial => return,
+                Match::Complete(mut call) => {
+                    call.start = self.pending_start;
+                    call.text = String::from_utf8_lossy(&self.pending).into_owned();
+                    out.push(Event::Call(call));
+                    for &byte in &self.pending {
+                        self.pending_start.advance(byte);
+                    }
+                    self.pending.clear();
+                }
+                Match::Fail(_, reason) => {
+                    if let Some(reason) = reason {
+                        out.push(Event::Rejected(Rejection {
+                            start: self.pending_start,
+                            text: String::from_utf8_lossy(&self.pending).into_owned(),
+                            reason,
+                        }));
+                    }
+                    let byte = self.pending.remove(0);
+                    self.pending_start.advance(byte);
+                }
+            }
+        }
+    }
+
+    /// A candidate only fails once no instruction can still match it. The
+    /// reason is taken from the instruction that matched the most bytes.
+    fn classify(&self, buf: &[u8]) -> Match {
+        let mut partial = false;
+        let mut best: (usize, Option<String>) = (0, None);
+        for (idx, def) in self.set.defs().iter().enumerate() {
+            match classify_def(def, buf) {
+                Match::Complete(mut call) => {
+                    call.def = idx;
+                    return Match::Complete(call);
+                }
+                Match::Partial => partial = true,
+                Match::Fail(at, reason) => {
+                    if at > best.0 || best.1.is_none() && at == best.0 {
+                        best = (at, reason);
+                    }
+                }
+            }
+        }
+        if partial {
+            Match::Partial
+        } else {
+            Match::Fail(best.0, best.1)
+        }
+    }
+}
+
+/// Matches `buf` against `name(` followed by `arity` comma separated operands
+/// and a closing `)`. Once the whole name has matched, a failure comes with
+/// the reason the rest of the candidate was rejected.
+fn classify_def(def: &InstructionDef, buf: &[u8]) -> Match {
+    let name = def.name.as_bytes();
+    let head_len = name.len() + 1;
+    if let Some(at) = buf.iter().zip(name).position(|(a, b)| a != b) {
+        return Match::Fail(at, None);
+    }
+    if buf.len() <= name.len() {
+        return Match::Partial;
+    }
+    if buf[name.len()] != b'(' {
+        return reject(
+            name.len(),
+            format!(
+                "expected '(' after {}, found {}",
+                def.name,
+                describe(buf[name.len()])
+            ),
+        );
+    }
+
+    let mut args: Vec<i64> = Vec::new();
+    let mut digits: Vec<u8> = Vec::new();
+    let body = &buf[head_len..];
+    for (i, &byte) in body.iter().enumerate() {
+        match byte {
+            b'0'..=b'9' if args.len() < def.arity => {
+                digits.push(byte);
+                if let Some(max) = def.max_digits.filter(|&max| digits.len() > max) {
+                    return reject(head_len + i, format!("operand longer than {} digits", max));
+                }
+            }
+            b',' | b')' if !digits.is_empty() => {
+                match parse_operand(&digits) {
+                    Some(value) => args.push(value),
+                    None => return reject(head_len + i, "operand out of range".to_string()),
+                }
+                digits.clear();
+                let is_last = args.len() == def.arity;
+                if byte == b',' && is_last {
+                    return reject(head_len + i, format!("more than {} operands", def.arity));
+                }
+                if byte == b')' && !is_last {
+                    return reject(
+                        head_len + i,
+                        format!("expected {} operands, found {}", def.arity, args.len()),
+                    );
+                }
+                if byte == b')' {
+                    return complete_if_last(i, body.len(), args);
+                }
+            }
+            b')' if def.arity == 0 => return complete_if_last(i, body.len(), args),
+            _ => {
+                let expected =
+                    if def.arity == 0 || args.len() + 1 == def.arity && !digits.is_empty() {
+                        "')'"
+                    } else if digits.is_empty() {
+                        "a digit"
+                    } else {
+                        "','"
+                    };
+                return reject(
+                    head_len + i,
+                    format!("expected {}, found {}", expected, describe(byte)),
+                );
+            }
+        }
+    }
+    Match::Partial
+}
+
+fn reject(at: usize, reason: String) -> Match {
+    Match::Fail(at, Some(reason))
+}
+
+fn describe(byte: u8) -> String {
+    format!("'{}'", (byte as char).escape_default())
+}
+
+fn complete_if_last(i: usize, len: usize, args: Vec<i64>) -> Match {
+    if i + 1 == len {
+        Match::Complete(Call {
+            def: 0,
+            args,
+            start: Position::start(),
+            text: String::new(),
+        })
+    } else {
+        Match::Fail(i, None)
+    }
+}
+
+fn parse_operand(digits: &[u8]) -> Option<i64> {
+    std::str::from_utf8(digits).ok()?.parse().ok()
+}
@@ -0,0 +1,94 @@
+use crate::scanner::{Call, Position, Rejection};
+use std::io::{self, Read};
+
+const GREEN: &str = "\x1b[32;1m";
+const RED: &str = "\x1b[31m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// One recognized instruction and how the interpreter treated it.
+pub struct TraceEntry {
+    pub start: Position,
+    pub text: String,
+    pub enabled: bool,
+    pub value: Option<i64>,
+}
+
+impl TraceEntry {
+    pub fn new(call: &Call, enabled: bool, value: Option<i64>) -> Self {
+        TraceEntry {
+            start: call.start,
+            text: call.text.clone(),
+            enabled,
+            value,
+        }
+    }
+}
+
+pub fn print_trace(entries: &[TraceEntry]) {
+    println!(
+        "{:>8}  {:>10}  {:<7}  {:<16}  value",
+        "offset", "line:col", "enabled", "instruction"
+    );
+    for entry in entries {
+        let line_col = format!("{}:{}", entry.start.line, entry.start.column);
+        let value = entry.value.map_or("-".to_string(), |v| v.to_string());
+        println!(
+            "{:>8}  {:>10}  {:<7}  {:<16}  {}",
+            entry.start.offset,
+            line_col,
+            if entry.enabled { "yes" } else { "no" },
+            entry.text,
+            value
+        );
+    }
+}
+
+pub fn print_near_misses(rejections: &[Rejection]) {
+    println!(
+        "{:>8}  {:>10}  {:<16}  reason",
+        "offset", "line:col", "near miss"
+    );
+    for rejection in rejections {
+        let line_col = format!("{}:{}", rejection.start.line, rejection.start.column);
+        println!(
+            "{:>8}  {:>10}  {:<16}  {}",
+            rejection.start.offset,
+            line_col,
+            rejection.text.escape_debug().to_string(),
+            rejection.reason
+        );
+    }
+}
+
+/// Prints the input with recognized instructions highlighted: green ones ran
+/// while enabled, red ones were disabled and the corrupted bytes are dimmed.
+pub fn print_annotated<R: Read>(mut reader: R, entries: &[TraceEntry]) -> io::Result<()> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    let mut out = String::new();
+    let mut cursor = 0;
+    for entry in entries {
+        let start = entry.start.offset;
+        let end = start + entry.text.len();
+        if start > cursor {
+            push_colored(&mut out, DIM, &data[cursor..start]);
+        }
+        push_colored(
+            &mut out,
+            if entry.enabled { GREEN } else { RED },
+            &data[start..end],
+        );
+        cursor = end;
+    }
+    push_colored(&mut out, DIM, &data[cursor..]);
+    println!("{}", out);
+    Ok(())
+}
+
+fn push_colored(out: &mut String, color: &str, bytes: &[u8]) {
+    out.push_str(color);
+    out.push_str(&String::from_utf8_lossy(bytes));
+    out.push_str(RESET);
+}
@@ -0,0 +1,95 @@
+use std::collections::{HashMap, VecDeque};
+
+struct Node {
+    next: HashMap<char, usize>,
+    fail: usize,
+    outputs: Vec<usize>,
+}
+
+impl Node {
+    fn new() -> Self {
+        Node {
+            next: HashMap::new(),
+            fail: 0,
+            outputs: Vec::new(),
+        }
+    }
+}
+
+/// Aho-Corasick automaton that finds every occurrence of a set of patterns in
+/// one pass over the text. Patterns are identified by their index in the list
+/// passed to `new`; duplicate patterns are reported separately.
+pub struct AhoCorasick {
+    nodes: Vec<Node>,
+    pattern_lens: Vec<usize>,
+}
+
+impl AhoCorasick {
+    pub fn new(patterns: &[Vec<char>]) -> Self {
+        let mut nodes = vec![Node::new()];
+        for (id, pattern) in patterns.iter().enumerate() {
+            let mut cur = 0;
+            for &c in pattern {
+                cur = match nodes[cur].next.get(&c) {
+                    Some(&next) => next,
+                    None => {
+                        nodes.push(Node::new());
+                        let next = nodes.len() - 1;
+                        nodes[cur].next.insert(c, next);
+                        next
+                    }
+                };
+            }
+            nodes[cur].outputs.push(id);
+        }
+
+        // Breadth first so a node's fail link is finished before its children
+        // need it. Each node inherits the outputs of its fail target.
+        let mut queue: VecDeque<usize> = nodes[0].next.values().copied().collect();
+        while let Some(node) = queue.pop_front() {
+            let edges: Vec<(char, usize)> =
+                nodes[node].next.iter().map(|(&c, &n)| (c, n)).collect();
+            for (c, child) in edges {
+                let mut fail = nodes[node].fail;
+                while fail != 0 && !nodes[fail].next.contains_key(&c) {
+                    fail = nodes[fail].fail;
+                }
+                nodes[child].fail = nodes[fail].next.get(&c).copied().unwrap_or(0);
+                let inherited = nodes[nodes[child].fail].outputs.clone();
+                nodes[child].outputs.extend(inherited);
+                queue.push_back(child);
+            }
+        }
+
+        AhoCorasick {
+            nodes,
+            pattern_lens: patterns.iter().map(|p| p.len()).collect(),
+        }
+    }
+
+    /// Returns `(start, pattern)` for every match, where `start` is the index
+    /// of the match's first character in `text`.
+    pub fn find_all(&self, text: impl IntoIterator<Item = char>) -> Vec<(usize, usize)> {
+        let mut matches = Vec::new();
+        let mut state = 0;
+        for (i, c) in text.into_iter().enumerate() {
+            state = self.step(state, c);
+            for &id in &self.nodes[state].outputs {
+                matches.push((i + 1 - self.pattern_lens[id], id));
+            }
+        }
+        matches
+    }
+
+    fn step(&self, mut state: usize, c: char) -> usize {
+        loop {
+            if let Some(&next) = self.nodes[state].next.get(&c) {
+                return next;
+            }
+            if state == 0 {
+                return 0;
+            }
+            state = self.nodes[state].fail;
+        }
+    }
+}
@@ -0,0 +1,98 @@
+/// Rows of the grid may have different lengths. Positions are checked against
+/// the row they land in, so a cell past the end of a shorter row is a hole
+/// that no word or pattern can pass through.
+///
+/// With `wrap` the grid is a torus as tall as the grid and as wide as its
+/// widest row: stepping off one edge comes back in on the opposite edge.
+pub fn grid_shape(data: &[Vec<char>]) -> (usize, usize) {
+    let width = data.iter().map(|row| row.len()).max().unwrap_or(0);
+    (data.len(), width)
+}
+
+pub fn cell_at(data: &[Vec<char>], pos: (usize, usize)) -> Option<char> {
+    data.get(pos.0).and_then(|row| row.get(pos.1)).copied()
+}
+
+/// Moves `steps` cells from `pos` in `dir`. Returns `None` when the target is
+/// off the grid (without `wrap`) or a hole in a ragged row.
+pub fn offset_pos(
+    data: &[Vec<char>],
+    pos: (usize, usize),
+    dir: (i32, i32),
+    steps: i32,
+    wrap: bool,
+) -> Option<(usize, usize)> {
+    let (nrows, ncols) = grid_shape(data);
+    if nrows == 0 || ncols == 0 {
+        return None;
+    }
+    let mut r = pos.0 as i64 + (dir.0 * steps) as i64;
+    let mut c = pos.1 as i64 + (dir.1 * steps) as i64;
+    if wrap {
+        r = r.rem_euclid(nrows as i64);
+        c = c.rem_euclid(ncols as i64);
+    }
+    if r < 0 || c < 0 {
+        return None;
+    }
+    let target = (r as usize, c as usize);
+    cell_at(data, target).map(|_| target)
+}
+
+pub fn step_in_dir(
+    data: &[Vec<char>],
+    pos: (usize, usize),
+    dir: (i32, i32),
+    wrap: bool,
+) -> Option<(usize, usize)> {
+    offset_pos(data, pos, dir, 1, wrap)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[&str]) -> Vec<Vec<char>> {
+        lines.iter().map(|l| l.chars().collect()).collect()
+    }
+
+    #[test]
+    fn hole_in_short_row_is_none() {
+        let data = rows(&["ABCD", "EF", "GHIJ"]);
+        assert_eq!(offset_pos(&data, (0, 3), (1, 0), 1, false), None);
+        assert_eq!(offset_pos(&data, (0, 3), (1, 0), 1, true), None);
+        assert_eq!(offset_pos(&data, (1, 1), (0, 1), 1, false), None);
+        assert_eq!(offset_pos(&data, (0, 1), (1, 0), 1, false), Some((1, 1)));
+    }
+
+    #[test]
+    fn off_grid_is_none_without_wrap() {
+        let data = rows(&["ABC", "DEF", "GHI"]);
+        assert_eq!(offset_pos(&data, (0, 0), (-1, 0), 1, false), None);
+        assert_eq!(offset_pos(&data, (0, 0), (0, -1), 1, false), None);
+        assert_eq!(offset_pos(&data, (2, 2), (1, 0), 1, false), None);
+        assert_eq!(offset_pos(&data, (2, 2), (0, 1), 1, false), None);
+    }
+
+    #[test]
+    fn wraps_at_every_edge() {
+        let data = rows(&["ABC", "DEF", "GHI"]);
+        assert_eq!(offset_pos(&data, (0, 1), (-1, 0), 1, true), Some((2, 1)));
+        assert_eq!(offset_pos(&data, (2, 1), (1, 0), 1, true), Some((0, 1)));
+        assert_eq!(offset_pos(&data, (1, 0), (0, -1), 1, true), Some((1, 2)));
+        assert_eq!(offset_pos(&data, (1, 2), (0, 1), 1, true), Some((1, 0)));
+        assert_eq!(offset_pos(&data, (0, 0), (-1, -1), 1, true), Some((2, 2)));
+        // More steps than the grid is wide still lands on the grid.
+        assert_eq!(offset_pos(&data, (0, 0), (0, -1), 7, true), Some((0, 2)));
+        assert_eq!(offset_pos(&data, (0, 0), (1, 1), 4, true), Some((1, 1)));
+    }
+
+    #[test]
+    fn wraps_to_widest_row() {
+        // The torus is as wide as the widest row, so wrapping from the end of
+        // a short row can land in a hole.
+        let data = rows(&["ABCD", "EF"]);
+        assert_eq!(offset_pos(&data, (1, 0), (0, -1), 1, true), None);
+        assert_eq!(offset_pos(&data, (0, 0), (0, -1), 1, true), Some((0, 3)));
+    }
+}
@@ -0,0 +1,44 @@
+use std::collections::HashSet;
+
+/// A word found in the grid, read from `start` one step at a time in
+/// `direction`. `cells` lists every covered cell in reading order.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WordMatch {
+    pub start: (usize, usize),
+    pub direction: (i32, i32),
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// A word crossed with itself on the diagonals around `center`. `cells` holds
+/// every covered cell.
+#[derive(Clone, Debug, PartialEq)]
+pub struct XMatch {
+    pub center: (usize, usize),
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// A placement of a pattern template. `origin` is where the template's top
+/// left corner landed and `variant` indexes the rotation or reflection used.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PatternMatch {
+    pub origin: (usize, usize),
+    pub variant: usize,
+    pub cells: Vec<(usize, usize)>,
+}
+
+/// Draws the grid like the puzzle statement: letters that belong to at least
+/// one match are kept and every other letter is replaced by `.`.
+pub fn render_highlighted<'a>(
+    data: &[Vec<char>],
+    matched_cells: impl IntoIterator<Item = &'a (usize, usize)>,
+) -> String {
+    let keep: HashSet<&(usize, usize)> = matched_cells.into_iter().collect();
+    let mut out = String::new();
+    for (r, row) in data.iter().enumerate() {
+        for (c, &ch) in row.iter().enumerate() {
+            out.push(if keep.contains(&(r, c)) { ch } else { '.' });
+        }
+        out.push('\n');
+    }
+    out
+}
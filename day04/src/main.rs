@@ -1,13 +1,62 @@
+mod aho_corasick;
+mod grid;
+mod grid_match;
+mod pattern;
+mod word_search;
+
+use grid::step_in_dir;
+use grid_match::{WordMatch, XMatch};
+use pattern::Template;
 use std::env::args;
 use std::fs::File;
 use std::io::{self, BufRead, Write};
 
 fn main() {
     let mut file_name: String = String::new();
+    let mut words: Vec<String> = Vec::new();
+    let mut list_matches = false;
+    let mut render = false;
+    let mut template: Option<Template> = None;
+    let mut rotations = false;
+    let mut reflections = false;
+    let mut wrap = false;
     let args: Vec<String> = args().collect();
-    if args.len() > 1 {
-        file_name = args[1].clone();
-    } else {
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--words" => {
+                let list = iter.next().expect("--words needs a comma separated list");
+                words.extend(list.split(',').map(|w| w.trim().to_string()));
+            }
+            "--word-list" => {
+                let list_file = iter.next().expect("--word-list needs a file name");
+                words.extend(read_word_list(list_file).expect("Error reading word list"));
+            }
+            "--matches" => list_matches = true,
+            "--render" => render = true,
+            "--pattern" => {
+                let pattern_file = iter.next().expect("--pattern needs a file name");
+                template =
+                    Some(Template::read_from_file(pattern_file).expect("Error reading pattern"));
+            }
+            "--shape" => {
+                // `x:WORD` or `plus:WORD`
+                let spec = iter.next().expect("--shape needs a shape");
+                let (shape, word) = spec.split_once(':').expect("--shape expects SHAPE:WORD");
+                template = Some(match shape {
+                    "x" => Template::x_shape(word),
+                    "plus" => Template::plus_shape(word),
+                    _ => panic!("Unknown shape '{}'", shape),
+                });
+            }
+            "--rotations" => rotations = true,
+            "--reflections" => reflections = true,
+            "--wrap" => wrap = true,
+            _ => file_name = arg.clone(),
+        }
+    }
+    words.retain(|w| !w.is_empty());
+    if file_name.is_empty() {
         print!("Enter file name: ");
         io::stdout().flush().expect("Failed to flush stdout");
 
@@ -19,11 +68,58 @@ fn main() {
 
     let data = read_from_file(&file_name).expect("Error reading from file");
 
-    let count = search_for_word(&data, "XMAS");
-    println!("XMAS Count: {}", count);
+    if let Some(template) = template {
+        let matches = pattern::search_for_pattern(&data, &template, rotations, reflections, wrap);
+        println!("Pattern Count: {}", matches.len());
+        if list_matches {
+            for m in &matches {
+                println!("  origin {:?} variant {}", m.origin, m.variant);
+            }
+        }
+        if render {
+            print!(
+                "{}",
+                grid_match::render_highlighted(&data, matches.iter().flat_map(|m| &m.cells))
+            );
+        }
+        return;
+    }
 
-    let count = search_for_x_shape(&data, "MAS");
-    println!("X-MAS Count: {}", count);
+    if !words.is_empty() {
+        let counts = word_search::search_for_words(&data, &words, wrap);
+        for (word, count) in words.iter().zip(counts) {
+            println!("{}: {}", word, count);
+        }
+        return;
+    }
+
+    let matches = search_for_word(&data, "XMAS", wrap);
+    println!("XMAS Count: {}", matches.len());
+    if list_matches {
+        for m in &matches {
+            println!("  start {:?} direction {:?}", m.start, m.direction);
+        }
+    }
+    if render {
+        print!(
+            "{}",
+            grid_match::render_highlighted(&data, matches.iter().flat_map(|m| &m.cells))
+        );
+    }
+
+    let matches = search_for_x_shape(&data, "MAS", wrap);
+    println!("X-MAS Count: {}", matches.len());
+    if list_matches {
+        for m in &matches {
+            println!("  center {:?}", m.center);
+        }
+    }
+    if render {
+        print!(
+            "{}",
+            grid_match::render_highlighted(&data, matches.iter().flat_map(|m| &m.cells))
+        );
+    }
 }
 
 fn read_from_file(file_name: &str) -> io::Result<Vec<Vec<char>>> {
@@ -37,70 +133,63 @@ fn read_from_file(file_name: &str) -> io::Result<Vec<Vec<char>>> {
         data.push(chars);
     }
 
-    return Ok(data);
+    Ok(data)
 }
 
-fn search_for_x_shape(data: &Vec<Vec<char>>, word: &str) -> i32 {
-    let mut count = 0;
-    let mid_char = word.chars().nth(1).expect("Error getting middle char");
+fn read_word_list(file_name: &str) -> io::Result<Vec<String>> {
+    let file = File::open(file_name)?;
+    let lines = io::BufReader::new(file).lines();
 
-    for r in 0..data.len() {
-        for c in 0..data[0].len() {
-            if data[r][c] != mid_char {
-                continue;
-            }
-            if validate_x_shape(data, word, (r, c)) {
-                count += 1;
-            }
-        }
+    let mut words: Vec<String> = Vec::new();
+    for line in lines {
+        words.push(line?.trim().to_string());
     }
-    return count;
+
+    Ok(words)
 }
 
-fn validate_x_shape(data: &Vec<Vec<char>>, word: &str, center_pos: (usize, usize)) -> bool {
-    if !check_1_padding((data.len(), data[0].len()), center_pos) {
-        return false;
-    }
-    let first_char = word.chars().nth(0).unwrap();
-    let last_char = word.chars().nth(2).unwrap();
-    for i in -1..2 {
-        if i == 0 {
-            continue;
-        }
-        let (a, b) = step_in_dir((center_pos.0, center_pos.1), (i, -1));
-        let (c, d) = step_in_dir((center_pos.0, center_pos.1), (-i, 1));
-        if !((data[a][b] == first_char && data[c][d] == last_char)
-            || (data[a][b] == last_char && data[c][d] == first_char))
-        {
-            return false;
-        }
-    }
-    return true;
+/// Counts `word` crossed with itself on the diagonals of a square, in any
+/// of the four orientations. This is the X template with rotations.
+fn search_for_x_shape(data: &[Vec<char>], word: &str, wrap: bool) -> Vec<XMatch> {
+    let template = Template::x_shape(word);
+    let half = word.chars().count() as i32 / 2;
+    pattern::search_for_pattern(data, &template, true, false, wrap)
+        .into_iter()
+        .map(|m| XMatch {
+            center: grid::offset_pos(data, m.origin, (1, 1), half, wrap).unwrap(),
+            cells: m.cells,
+        })
+        .collect()
 }
 
-fn search_for_word(data: &Vec<Vec<char>>, word: &str) -> i32 {
-    let mut count = 0;
-    for r in 0..data.len() {
-        for c in 0..data[0].len() {
-            if data[r][c] != word.chars().nth(0).unwrap() {
+fn search_for_word(data: &[Vec<char>], word: &str, wrap: bool) -> Vec<WordMatch> {
+    let mut matches = Vec::new();
+    for (r, row) in data.iter().enumerate() {
+        for (c, &ch) in row.iter().enumerate() {
+            if ch != word.chars().next().unwrap() {
                 continue;
             }
-            count += search_from_center(data, word, (r, c));
+            matches.extend(search_from_center(data, word, (r, c), wrap));
         }
     }
-    return count;
+    matches
 }
 
-fn search_from_center(data: &Vec<Vec<char>>, word: &str, c_pos: (usize, usize)) -> i32 {
-    let mut count = 0;
+fn search_from_center(
+    data: &[Vec<char>],
+    word: &str,
+    c_pos: (usize, usize),
+    wrap: bool,
+) -> Vec<WordMatch> {
+    let mut matches = Vec::new();
     for i in -1..2 {
         for j in -1..2 {
-            if (i == 0 && j == 0)
-                || !check_step_inbounds((data.len(), data[0].len()), (c_pos.0, c_pos.1), (i, j))
-            {
+            if i == 0 && j == 0 {
                 continue;
             }
-            let (a, b) = step_in_dir((c_pos.0, c_pos.1), (i, j));
+            let Some((a, b)) = step_in_dir(data, c_pos, (i, j), wrap) else {
+                continue;
+            };
             if data[a][b] != word.chars().nth(1).unwrap() {
                 continue;
             }
@@ -109,32 +198,47 @@ fn search_from_center(data: &Vec<Vec<char>>, word: &str, c_pos: (usize, usize))
                 .nth(2)
                 .map(|(i, _)| i)
                 .unwrap_or(word.len());
-            if search_direction(data, word[start_idx..].chars().collect(), (a, b), (i, j)) {
-                count += 1;
+            if search_direction(
+                data,
+                word[start_idx..].chars().collect(),
+                (a, b),
+                (i, j),
+                wrap,
+            ) {
+                let mut cells = vec![c_pos, (a, b)];
+                for _ in 2..word.chars().count() {
+                    let next = step_in_dir(data, *cells.last().unwrap(), (i, j), wrap);
+                    cells.push(next.unwrap());
+                }
+                matches.push(WordMatch {
+                    start: c_pos,
+                    direction: (i, j),
+                    cells,
+                });
             }
         }
     }
-    return count;
+    matches
 }
 
 fn search_direction(
-    data: &Vec<Vec<char>>,
+    data: &[Vec<char>],
     word_chars: Vec<char>,
     start_pos: (usize, usize),
     dir: (i32, i32),
+    wrap: bool,
 ) -> bool {
     let mut pos = start_pos;
     let mut i = 0;
 
-    if word_chars.len() == 0 {
+    if word_chars.is_empty() {
         return true;
     }
 
     while i < word_chars.len() {
-        if check_step_inbounds((data.len(), data[0].len()), pos, dir) {
-            pos = step_in_dir(pos, dir);
-        } else {
-            break;
+        match step_in_dir(data, pos, dir, wrap) {
+            Some(next) => pos = next,
+            None => break,
         }
         if data[pos.0][pos.1] == word_chars[i] {
             i += 1;
@@ -145,21 +249,62 @@ fn search_direction(
             return false;
         }
     }
-    return false;
+    false
 }
 
-fn check_1_padding(data_shape: (usize, usize), pos: (usize, usize)) -> bool {
-    return pos.0 >= 1 && pos.0 < data_shape.0 - 1 && pos.1 >= 1 && pos.1 < data_shape.1 - 1;
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-fn check_step_inbounds(data_shape: (usize, usize), pos: (usize, usize), dir: (i32, i32)) -> bool {
-    return !((pos.0 as i32 + dir.0 < 0 || pos.0 as i32 + dir.0 >= data_shape.0 as i32)
-        || (pos.1 as i32 + dir.1 < 0 || pos.1 as i32 + dir.1 >= data_shape.1 as i32));
-}
+    fn xmas_counts(data: &[Vec<char>], wrap: bool) -> (usize, usize) {
+        (
+            search_for_word(data, "XMAS", wrap).len(),
+            search_for_x_shape(data, "MAS", wrap).len(),
+        )
+    }
+
+    fn words_count(data: &[Vec<char>], word: &str, wrap: bool) -> usize {
+        word_search::search_for_words(data, &[word.to_string()], wrap)[0]
+    }
+
+    #[test]
+    fn example_counts() {
+        let data = read_from_file("test_input.txt").unwrap();
+        assert_eq!(xmas_counts(&data, false), (18, 9));
+        assert_eq!(words_count(&data, "XMAS", false), 18);
+    }
 
-fn step_in_dir(pos: (usize, usize), dir: (i32, i32)) -> (usize, usize) {
-    return (
-        (pos.0 as i32 + dir.0) as usize,
-        (pos.1 as i32 + dir.1) as usize,
-    );
+    #[test]
+    fn ragged_rows() {
+        let data = read_from_file("test_input_ragged.txt").unwrap();
+        assert!(data.iter().any(|row| row.len() != data[0].len()));
+        assert_eq!(xmas_counts(&data, false), (8, 5));
+        assert_eq!(words_count(&data, "XMAS", false), 8);
+        // Wrapping must not step into holes either.
+        assert_eq!(
+            search_for_word(&data, "XMAS", true).len(),
+            words_count(&data, "XMAS", true)
+        );
+    }
+
+    #[test]
+    fn torus() {
+        let data = read_from_file("test_input_torus.txt").unwrap();
+        assert_eq!(search_for_word(&data, "XMAS", false).len(), 0);
+        assert_eq!(words_count(&data, "XMAS", false), 0);
+        assert_eq!(search_for_word(&data, "XMAS", true).len(), 2);
+        assert_eq!(words_count(&data, "XMAS", true), 2);
+    }
+
+    #[test]
+    fn word_searches_agree_on_input() {
+        let data = read_from_file("input.txt").unwrap();
+        for wrap in [false, true] {
+            assert_eq!(
+                search_for_word(&data, "XMAS", wrap).len(),
+                words_count(&data, "XMAS", wrap)
+            );
+        }
+        assert_eq!(words_count(&data, "XMAS", true), 2444);
+    }
 }
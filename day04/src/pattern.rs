@@ -0,0 +1,190 @@
+use crate::grid;
+use crate::grid_match::PatternMatch;
+use std::fs::File;
+use std::io::{self, BufRead};
+
+/// Marks a cell of a template file that can hold any letter.
+pub const WILDCARD: char = '.';
+
+/// A 2D arrangement of letters. `cells` holds `(row, col, letter)` offsets
+/// from the template's top left corner; wildcard cells are left out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Template {
+    cells: Vec<(i32, i32, char)>,
+}
+
+impl Template {
+    /// Builds a template from rows of text, e.g. `["M.S", ".A.", "M.S"]`.
+    pub fn from_rows(rows: &[String]) -> Self {
+        let mut cells = Vec::new();
+        for (r, row) in rows.iter().enumerate() {
+            for (c, ch) in row.chars().enumerate() {
+                if ch != WILDCARD && !ch.is_whitespace() {
+                    cells.push((r as i32, c as i32, ch));
+                }
+            }
+        }
+        Template::normalized(cells)
+    }
+
+    pub fn read_from_file(file_name: &str) -> io::Result<Self> {
+        let file = File::open(file_name)?;
+        let rows: Vec<String> = io::BufReader::new(file)
+            .lines()
+            .collect::<io::Result<_>>()?;
+        Ok(Template::from_rows(&rows))
+    }
+
+    /// `word` written along both diagonals of a square, crossing in the middle.
+    pub fn x_shape(word: &str) -> Self {
+        let chars: Vec<char> = word.chars().collect();
+        let n = chars.len() as i32 - 1;
+        let mut cells = Vec::new();
+        for (i, &ch) in chars.iter().enumerate() {
+            cells.push((i as i32, i as i32, ch));
+            cells.push((n - i as i32, i as i32, ch));
+        }
+        Template::normalized(cells)
+    }
+
+    /// `word` written along the middle row and the middle column.
+    pub fn plus_shape(word: &str) -> Self {
+        let chars: Vec<char> = word.chars().collect();
+        let mid = chars.len() as i32 / 2;
+        let mut cells = Vec::new();
+        for (i, &ch) in chars.iter().enumerate() {
+            cells.push((mid, i as i32, ch));
+            cells.push((i as i32, mid, ch));
+        }
+        Template::normalized(cells)
+    }
+
+    /// The template together with its rotations and/or mirror images. Variants
+    /// that end up identical are only kept once so no placement counts twice.
+    pub fn variants(&self, rotations: bool, reflections: bool) -> Vec<Template> {
+        let mut bases = vec![self.clone()];
+        if reflections {
+            bases.push(Template::normalized(
+                self.cells.iter().map(|&(r, c, ch)| (r, -c, ch)).collect(),
+            ));
+        }
+
+        let mut variants: Vec<Template> = Vec::new();
+        for base in bases {
+            let mut current = base;
+            let turns = if rotations { 4 } else { 1 };
+            for _ in 0..turns {
+                if !variants.contains(&current) {
+                    variants.push(current.clone());
+                }
+                current = Template::normalized(
+                    current
+                        .cells
+                        .iter()
+                        .map(|&(r, c, ch)| (c, -r, ch))
+                        .collect(),
+                );
+            }
+        }
+        variants
+    }
+
+    /// Shifts the cells so the smallest row and column are 0 and sorts them, so
+    /// equal shapes compare equal. Overlapping cells are merged.
+    fn normalized(mut cells: Vec<(i32, i32, char)>) -> Self {
+        let min_r = cells.iter().map(|&(r, _, _)| r).min().unwrap_or(0);
+        let min_c = cells.iter().map(|&(_, c, _)| c).min().unwrap_or(0);
+        for cell in cells.iter_mut() {
+            cell.0 -= min_r;
+            cell.1 -= min_c;
+        }
+        cells.sort();
+        cells.dedup();
+        Template { cells }
+    }
+}
+
+/// Finds every placement of any variant of `template` in the grid. The top
+/// left corner of a template may be a wildcard, so every position of the
+/// padded grid is tried as an origin, including holes in ragged rows.
+pub fn search_for_pattern(
+    data: &[Vec<char>],
+    template: &Template,
+    rotations: bool,
+    reflections: bool,
+    wrap: bool,
+) -> Vec<PatternMatch> {
+    let mut matches = Vec::new();
+    let variants = template.variants(rotations, reflections);
+    let (nrows, ncols) = grid::grid_shape(data);
+    for r in 0..nrows {
+        for c in 0..ncols {
+            for (v, variant) in variants.iter().enumerate() {
+                if let Some(cells) = place(data, variant, (r, c), wrap) {
+                    matches.push(PatternMatch {
+                        origin: (r, c),
+                        variant: v,
+                        cells,
+                    });
+                }
+            }
+        }
+    }
+    matches
+}
+
+/// Returns the covered cells if `template` fits with its top left corner at
+/// `origin`.
+fn place(
+    data: &[Vec<char>],
+    template: &Template,
+    origin: (usize, usize),
+    wrap: bool,
+) -> Option<Vec<(usize, usize)>> {
+    let mut cells = Vec::with_capacity(template.cells.len());
+    for &(dr, dc, ch) in &template.cells {
+        let pos = grid::offset_pos(data, origin, (dr, dc), 1, wrap)?;
+        if grid::cell_at(data, pos) != Some(ch) {
+            return None;
+        }
+        cells.push(pos);
+    }
+    Some(cells)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rows(lines: &[&str]) -> Vec<Vec<char>> {
+        lines.iter().map(|l| l.chars().collect()).collect()
+    }
+
+    #[test]
+    fn pattern_wraps_around_edges() {
+        let template = Template::x_shape("MAS");
+        let data = rows(&["SM.", "..A", "SM."]);
+        assert!(search_for_pattern(&data, &template, false, false, false).is_empty());
+        let matches = search_for_pattern(&data, &template, false, false, true);
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].origin, (0, 1));
+        assert_eq!(
+            matches[0].cells,
+            vec![(0, 1), (0, 0), (1, 2), (2, 1), (2, 0)]
+        );
+    }
+
+    #[test]
+    fn pattern_skips_holes_in_ragged_rows() {
+        let template = Template::x_shape("MAS");
+        let full = rows(&["M.S", ".A.", "M.S"]);
+        assert_eq!(
+            search_for_pattern(&full, &template, false, false, false).len(),
+            1
+        );
+        let ragged = rows(&["M.S", ".", "M.S"]);
+        for wrap in [false, true] {
+            assert!(search_for_pattern(&ragged, &template, true, true, wrap).is_empty());
+        }
+    }
+}
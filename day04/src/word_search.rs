@@ -0,0 +1,124 @@
+use crate::aho_corasick::AhoCorasick;
+use crate::grid;
+use std::collections::HashSet;
+
+/// Stands in for holes in ragged rows. It never appears in a word, so a word
+/// can't be matched across a hole.
+const HOLE: char = '\0';
+
+/// The four directions a line is read in. Reading a line backwards covers the
+/// other four.
+const LINE_DIRECTIONS: [(i32, i32); 4] = [(0, 1), (1, 0), (1, 1), (1, -1)];
+
+/// Every straight line through the grid that a word can lie on: rows,
+/// columns, diagonals and anti-diagonals, laid over the grid padded to its
+/// widest row. Each line lists its cells in order.
+fn grid_lines(data: &[Vec<char>]) -> Vec<Vec<(usize, usize)>> {
+    let (nrows, ncols) = grid::grid_shape(data);
+    let mut lines: Vec<Vec<(usize, usize)>> = Vec::new();
+    if nrows == 0 || ncols == 0 {
+        return lines;
+    }
+
+    for r in 0..nrows {
+        lines.push((0..ncols).map(|c| (r, c)).collect());
+    }
+    for c in 0..ncols {
+        lines.push((0..nrows).map(|r| (r, c)).collect());
+    }
+    // Diagonals start on the top row or the left column, anti-diagonals on the
+    // top row or the right column.
+    for start in 0..nrows + ncols - 1 {
+        let (r0, c0) = if start < ncols {
+            (0, start)
+        } else {
+            (start - ncols + 1, 0)
+        };
+        lines.push(
+            (0..)
+                .map(|i| (r0 + i, c0 + i))
+                .take_while(|&(r, c)| r < nrows && c < ncols)
+                .collect(),
+        );
+        let (r0, c0) = if start < ncols {
+            (0, start)
+        } else {
+            (start - ncols + 1, ncols - 1)
+        };
+        lines.push(
+            (0..)
+                .map(|i| (r0 + i, c0 as i64 - i as i64))
+                .take_while(|&(r, c)| r < nrows && c >= 0)
+                .map(|(r, c)| (r, c as usize))
+                .collect(),
+        );
+    }
+    lines
+}
+
+/// On a torus every line closes into a cycle. Stepping in a fixed direction
+/// is a bijection on the padded grid, so following it from each position not
+/// yet seen splits the grid into disjoint cycles.
+fn torus_cycles(data: &[Vec<char>]) -> Vec<Vec<(usize, usize)>> {
+    let (nrows, ncols) = grid::grid_shape(data);
+    let mut cycles: Vec<Vec<(usize, usize)>> = Vec::new();
+    for dir in LINE_DIRECTIONS {
+        let mut seen: HashSet<(usize, usize)> = HashSet::new();
+        for r in 0..nrows {
+            for c in 0..ncols {
+                let mut pos = (r, c);
+                let mut cycle = Vec::new();
+                while seen.insert(pos) {
+                    cycle.push(pos);
+                    pos = (
+                        (pos.0 as i32 + dir.0).rem_euclid(nrows as i32) as usize,
+                        (pos.1 as i32 + dir.1).rem_euclid(ncols as i32) as usize,
+                    );
+                }
+                if !cycle.is_empty() {
+                    cycles.push(cycle);
+                }
+            }
+        }
+    }
+    cycles
+}
+
+/// Counts every occurrence of each word in all eight directions. The words
+/// and their reversals go into one automaton, so each line is scanned once
+/// and a match of a reversed word is the word read backwards. With `wrap`
+/// each cycle is scanned once around plus enough extra cells for a word that
+/// starts near its end to wrap back onto its start.
+pub fn search_for_words(data: &[Vec<char>], words: &[String], wrap: bool) -> Vec<usize> {
+    let mut patterns: Vec<Vec<char>> = Vec::new();
+    for word in words {
+        patterns.push(word.chars().collect());
+        patterns.push(word.chars().rev().collect());
+    }
+    let automaton = AhoCorasick::new(&patterns);
+    let longest = patterns.iter().map(|p| p.len()).max().unwrap_or(0);
+    let char_at = |&(r, c): &(usize, usize)| grid::cell_at(data, (r, c)).unwrap_or(HOLE);
+
+    let mut counts = vec![0; words.len()];
+    if wrap {
+        for cycle in torus_cycles(data) {
+            let text = cycle
+                .iter()
+                .cycle()
+                .take(cycle.len() + longest.saturating_sub(1))
+                .map(char_at);
+            for (start, id) in automaton.find_all(text) {
+                if start < cycle.len() {
+                    counts[id / 2] += 1;
+                }
+            }
+        }
+    } else {
+        for line in grid_lines(data) {
+            for (_, id) in automaton.find_all(line.iter().map(char_at)) {
+                counts[id / 2] += 1;
+            }
+        }
+    }
+    counts
+}
@@ -0,0 +1,174 @@
+use crate::edge_set::EdgeSet;
+use std::collections::HashMap;
+
+/// Every ordering implied by a rule set, directly or through a chain of rules,
+/// stored as a dense reachability matrix so lookups don't walk the graph.
+pub struct Closure {
+    pages: Vec<i32>,
+    index: HashMap<i32, usize>,
+    reachable: Vec<bool>,
+}
+
+impl Closure {
+    pub fn new(g: &EdgeSet) -> Self {
+        let nodes = g.nodes();
+        let n = nodes.len();
+        let index: HashMap<i32, usize> = nodes.iter().enumerate().map(|(i, &p)| (p, i)).collect();
+        let mut reachable = vec![false; n * n];
+        for (i, &start) in nodes.iter().enumerate() {
+            let row = &mut reachable[i * n..(i + 1) * n];
+            let mut stack: Vec<i32> = g.neighbors(start).collect();
+            while let Some(node) = stack.pop() {
+                let j = index[&node];
+                if row[j] {
+                    continue;
+                }
+                row[j] = true;
+                stack.extend(g.neighbors(node));
+            }
+        }
+        Closure {
+            pages: nodes,
+            index,
+            reachable,
+        }
+    }
+
+    /// True when some chain of rules puts `a` before `b`. Pages that aren't in
+    /// any rule are unconstrained.
+    pub fn must_precede(&self, a: i32, b: i32) -> bool {
+        match (self.index.get(&a), self.index.get(&b)) {
+            (Some(&i), Some(&j)) => self.reachable[i * self.index.len() + j],
+            _ => false,
+        }
+    }
+
+    /// True when `a` and `b` are the same page or lie on a common cycle of
+    /// rules, i.e. they are in the same strongly connected component.
+    pub fn same_component(&self, a: i32, b: i32) -> bool {
+        a == b || (self.must_precede(a, b) && self.must_precede(b, a))
+    }
+}
+
+/// Rules that add nothing to the rule set, and pairs of pages that the rules
+/// put in both orders.
+pub struct RuleReport {
+    /// Rules `a|b` where `a` already precedes `b` through a page outside both
+    /// their cycles. Judged on the condensation, the rules with every cycle
+    /// collapsed to one page, so the whole list can be dropped at once
+    /// without changing the closure. Rules inside a cycle are never listed;
+    /// on an acyclic rule set this is the plain transitive reduction.
+    pub redundant: Vec<(i32, i32)>,
+    /// Pairs `(a, b)` with `a < b` where `a` must precede `b` and `b` must
+    /// precede `a`.
+    pub contradictory: Vec<(i32, i32)>,
+}
+
+pub fn rule_report(g: &EdgeSet, closure: &Closure) -> RuleReport {
+    let nodes = g.nodes();
+    let mut redundant = Vec::new();
+    for &src in &nodes {
+        let mut dests: Vec<i32> = g.neighbors(src).collect();
+        dests.sort();
+        for &dest in &dests {
+            if is_implied(closure, src, dest) {
+                redundant.push((src, dest));
+            }
+        }
+    }
+
+    let mut contradictory = Vec::new();
+    for (i, &a) in nodes.iter().enumerate() {
+        for &b in &nodes[i + 1..] {
+            if closure.must_precede(a, b) && closure.must_precede(b, a) {
+                contradictory.push((a, b));
+            }
+        }
+    }
+    RuleReport {
+        redundant,
+        contradictory,
+    }
+}
+
+/// Whether the rule `src|dest` is implied by a chain of rules through a page
+/// in neither `src`'s nor `dest`'s cycle. See `RuleReport::redundant`.
+pub fn is_implied(closure: &Closure, src: i32, dest: i32) -> bool {
+    if closure.same_component(src, dest) {
+        return false;
+    }
+    closure.pages.iter().any(|&mid| {
+        !closure.same_component(mid, src)
+            && !closure.same_component(mid, dest)
+            && closure.must_precede(src, mid)
+            && closure.must_precede(mid, dest)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edge_set(rules: &[(i32, i32)]) -> EdgeSet {
+        let mut g = EdgeSet::new();
+        for &(src, dest) in rules {
+            g.add_edge(src, dest);
+        }
+        g
+    }
+
+    fn same_closure(a: &EdgeSet, b: &EdgeSet) -> bool {
+        let (ca, cb) = (a.transitive_closure(), b.transitive_closure());
+        let pages = a.nodes();
+        pages.iter().all(|&x| {
+            pages
+                .iter()
+                .all(|&y| ca.must_precede(x, y) == cb.must_precede(x, y))
+        })
+    }
+
+    #[test]
+    fn acyclic_redundant_rules() {
+        let g = edge_set(&[(1, 2), (2, 3), (1, 3), (3, 4), (1, 4)]);
+        let report = rule_report(&g, &g.transitive_closure());
+        assert_eq!(report.redundant, vec![(1, 3), (1, 4)]);
+        assert!(report.contradictory.is_empty());
+    }
+
+    #[test]
+    fn rules_in_a_cycle_are_not_redundant() {
+        // 1 -> 2 -> 3 -> 1 is a cycle; 1|4 is implied through 5 only.
+        let g = edge_set(&[(1, 2), (2, 3), (3, 1), (1, 3), (3, 5), (5, 4), (1, 4)]);
+        let report = rule_report(&g, &g.transitive_closure());
+        assert_eq!(report.redundant, vec![(1, 4)]);
+        assert_eq!(report.contradictory, vec![(1, 2), (1, 3), (2, 3)]);
+    }
+
+    #[test]
+    fn dropping_all_redundant_rules_keeps_closure() {
+        let mut state: u64 = 7;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        for _ in 0..300 {
+            let mut rules = Vec::new();
+            for a in 0..7 {
+                for b in 0..7 {
+                    if a != b && next(5) == 0 {
+                        rules.push((a, b));
+                    }
+                }
+            }
+            let g = edge_set(&rules);
+            let report = rule_report(&g, &g.transitive_closure());
+            let mut reduced = g.clone();
+            for &(src, dest) in &report.redundant {
+                reduced.remove_edge(src, dest);
+            }
+            assert!(same_closure(&g, &reduced), "rules {:?}", rules);
+        }
+    }
+}
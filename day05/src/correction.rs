@@ -0,0 +1,467 @@
+use crate::closure::Closure;
+use crate::edge_set::EdgeSet;
+use crate::topo_sort::{self, Cycle, TieBreak};
+use std::collections::HashMap;
+use std::fmt;
+
+/// How an invalid update is put back in order.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Correction {
+    /// Plain topological sort, pages may move even if they were fine.
+    Topo,
+    /// Move as few pages as possible.
+    Moves,
+    /// Use as few swaps of neighbouring pages as possible, searching all
+    /// valid orders.
+    Swaps,
+}
+
+impl Correction {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "topo" => Ok(Correction::Topo),
+            "moves" => Ok(Correction::Moves),
+            "swaps" => Ok(Correction::Swaps),
+            _ => Err(format!("unknown correction mode '{}'", s)),
+        }
+    }
+}
+
+/// One step of an edit script. Positions are 0 based.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Edit {
+    /// Take `page` out at `from` and put it back in so it ends up at `to`.
+    /// Both refer to the update as left by the earlier edits.
+    Move { page: i32, from: usize, to: usize },
+    /// Swap the pages at `pos` and `pos + 1`, applied in script order.
+    Swap { pos: usize, left: i32, right: i32 },
+}
+
+impl fmt::Display for Edit {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Edit::Move { page, from, to } => write!(f, "move {} from {} to {}", page, from, to),
+            Edit::Swap { pos, left, right } => {
+                write!(f, "swap {} and {} at {}", left, right, pos)
+            }
+        }
+    }
+}
+
+/// A corrected update. `unique` is true when the rules allow no other order.
+pub struct Corrected {
+    pub order: Vec<i32>,
+    pub unique: bool,
+    pub edits: Vec<Edit>,
+}
+
+/// Reorders `seq` so it satisfies `subgraph`, the rules between its pages.
+/// `tie_break` only applies to `Correction::Topo`.
+pub fn correct(
+    subgraph: &EdgeSet,
+    seq: &[i32],
+    mode: Correction,
+    tie_break: TieBreak,
+) -> Result<Corrected, Cycle> {
+    let sorted = topo_sort::topo_sort(subgraph, tie_break, seq)?;
+    let unique = sorted.unique;
+    let order = match mode {
+        Correction::Topo => sorted.order,
+        Correction::Swaps => fewest_swaps_order(subgraph, &subgraph.transitive_closure(), seq),
+        Correction::Moves => {
+            let keep = largest_kept_set(&subgraph.transitive_closure(), seq);
+            // Chaining the kept pages in their original order adds no cycle,
+            // because no two of them are out of order.
+            let mut pinned = subgraph.clone();
+            for pair in keep.windows(2) {
+                pinned.add_edge(seq[pair[0]], seq[pair[1]]);
+            }
+            topo_sort::topo_sort(&pinned, TieBreak::Original, seq)?.order
+        }
+    };
+
+    let edits = match mode {
+        Correction::Swaps => swap_script(seq, &order),
+        _ => move_script(seq, &order),
+    };
+    Ok(Corrected {
+        order,
+        unique,
+        edits,
+    })
+}
+
+/// The largest set of positions in `seq` whose pages are already in an order
+/// the rules allow. Every other page has to move, so this is the set that
+/// stays put. Exact search; updates are short.
+fn largest_kept_set(closure: &Closure, seq: &[i32]) -> Vec<usize> {
+    let n = seq.len();
+    let compatible = |i: usize, j: usize| {
+        let (i, j) = (i.min(j), i.max(j));
+        !closure.must_precede(seq[j], seq[i])
+    };
+
+    fn grow(
+        chosen: &mut Vec<usize>,
+        candidates: &[usize],
+        best: &mut Vec<usize>,
+        compatible: &dyn Fn(usize, usize) -> bool,
+    ) {
+        if chosen.len() + candidates.len() <= best.len() {
+            return;
+        }
+        let Some((&first, rest)) = candidates.split_first() else {
+            *best = chosen.clone();
+            return;
+        };
+        let narrowed: Vec<usize> = rest
+            .iter()
+            .copied()
+            .filter(|&c| compatible(first, c))
+            .collect();
+        chosen.push(first);
+        grow(chosen, &narrowed, best, compatible);
+        chosen.pop();
+        grow(chosen, rest, best, compatible);
+    }
+
+    let candidates: Vec<usize> = (0..n).collect();
+    let mut best = Vec::new();
+    grow(&mut Vec::new(), &candidates, &mut best, &compatible);
+    best
+}
+
+/// The valid order with the fewest inverted pairs relative to `seq`, which is
+/// the fewest swaps of neighbouring pages. Builds the order front to back:
+/// placing a page inverts it with every earlier page of `seq` not placed yet.
+/// Branch and bound, pruned by the pairs the rules force apart and by the
+/// best cost already seen for each set of placed pages. Exact search; updates
+/// are short. Beyond 64 pages the placed set no longer fits the bit mask and
+/// this falls back to taking the first ready page of `seq`.
+fn fewest_swaps_order(subgraph: &EdgeSet, closure: &Closure, seq: &[i32]) -> Vec<i32> {
+    let n = seq.len();
+    if n > 64 {
+        return topo_sort::topo_sort(subgraph, TieBreak::Original, seq)
+            .expect("cycle already ruled out")
+            .order;
+    }
+    // `preds[i]`: positions whose pages have a rule putting them before
+    // `seq[i]`. `forced[i]`: positions that must end up on the other side
+    // of `i` from where they are in `seq`.
+    let mut preds = vec![0u64; n];
+    let mut forced = vec![0u64; n];
+    for i in 0..n {
+        for j in 0..n {
+            if subgraph.has_edge(seq[j], seq[i]) {
+                preds[i] |= 1 << j;
+            }
+            let inverted = if j < i {
+                closure.must_precede(seq[i], seq[j])
+            } else {
+                closure.must_precede(seq[j], seq[i])
+            };
+            if i != j && inverted {
+                forced[i] |= 1 << j;
+            }
+        }
+    }
+    let forced_total: u32 = forced.iter().map(|f| f.count_ones()).sum::<u32>() / 2;
+
+    struct Search<'a> {
+        preds: &'a [u64],
+        forced: &'a [u64],
+        best_cost: u32,
+        best: Vec<usize>,
+        seen: HashMap<u64, u32>,
+    }
+
+    fn place(
+        search: &mut Search,
+        placed: u64,
+        cost: u32,
+        forced_left: u32,
+        order: &mut Vec<usize>,
+    ) {
+        let n = search.preds.len();
+        if order.len() == n {
+            if cost < search.best_cost {
+                search.best_cost = cost;
+                search.best = order.clone();
+            }
+            return;
+        }
+        if cost + forced_left >= search.best_cost {
+            return;
+        }
+        match search.seen.get(&placed) {
+            Some(&seen_cost) if seen_cost <= cost => return,
+            _ => {
+                search.seen.insert(placed, cost);
+            }
+        }
+        // Trying pages in their original order finds a good bound early.
+        for i in 0..n {
+            let bit = 1u64 << i;
+            if placed & bit != 0 || search.preds[i] & !placed != 0 {
+                continue;
+            }
+            let unplaced = !placed & !bit;
+            let added = (unplaced & (bit - 1)).count_ones();
+            let resolved = (search.forced[i] & unplaced).count_ones();
+            order.push(i);
+            place(
+                search,
+                placed | bit,
+                cost + added,
+                forced_left - resolved,
+                order,
+            );
+            order.pop();
+        }
+    }
+
+    let mut search = Search {
+        preds: &preds,
+        forced: &forced,
+        best_cost: u32::MAX,
+        best: Vec::new(),
+        seen: HashMap::new(),
+    };
+    place(&mut search, 0, 0, forced_total, &mut Vec::new());
+    search.best.iter().map(|&i| seq[i]).collect()
+}
+
+/// Moves every page outside the longest common subsequence of `seq` and
+/// `order` once, in the order they appear in `order`. Each goes right after
+/// the page before it in `order` among those already in their final place,
+/// so once all are moved the update matches `order`.
+fn move_script(seq: &[i32], order: &[i32]) -> Vec<Edit> {
+    let mut placed: Vec<i32> = common_subsequence(seq, order);
+    let mut current = seq.to_vec();
+    let mut edits = Vec::new();
+    for (t, &page) in order.iter().enumerate() {
+        if placed.contains(&page) {
+            continue;
+        }
+        let from = current.iter().position(|&p| p == page).unwrap();
+        current.remove(from);
+        let to = order[..t]
+            .iter()
+            .rev()
+            .find(|p| placed.contains(p))
+            .map_or(0, |prev| {
+                current.iter().position(|p| p == prev).unwrap() + 1
+            });
+        current.insert(to, page);
+        placed.push(page);
+        if from != to {
+            edits.push(Edit::Move { page, from, to });
+        }
+    }
+    edits
+}
+
+fn common_subsequence(a: &[i32], b: &[i32]) -> Vec<i32> {
+    let (n, m) = (a.len(), b.len());
+    let mut len = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            len[i][j] = if a[i] == b[j] {
+                len[i + 1][j + 1] + 1
+            } else {
+                len[i + 1][j].max(len[i][j + 1])
+            };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut common = Vec::new();
+    while i < n && j < m {
+        if a[i] == b[j] {
+            common.push(a[i]);
+            i += 1;
+            j += 1;
+        } else if len[i + 1][j] >= len[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    common
+}
+
+/// Bubble sort towards `order`, recording every swap. Bubble sort swaps each
+/// inverted pair exactly once, so the script is as short as possible.
+fn swap_script(seq: &[i32], order: &[i32]) -> Vec<Edit> {
+    let rank = |page: i32| order.iter().position(|&p| p == page).unwrap();
+    let mut current = seq.to_vec();
+    let mut edits = Vec::new();
+    for end in (1..current.len()).rev() {
+        for pos in 0..end {
+            if rank(current[pos]) > rank(current[pos + 1]) {
+                edits.push(Edit::Swap {
+                    pos,
+                    left: current[pos],
+                    right: current[pos + 1],
+                });
+                current.swap(pos, pos + 1);
+            }
+        }
+    }
+    edits
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn subgraph(rules: &[(i32, i32)], seq: &[i32]) -> EdgeSet {
+        let mut g = EdgeSet::new();
+        for &page in seq {
+            g.add_node(page);
+        }
+        for &(src, dest) in rules {
+            g.add_edge(src, dest);
+        }
+        g
+    }
+
+    fn replay(seq: &[i32], edits: &[Edit]) -> Vec<i32> {
+        let mut current = seq.to_vec();
+        for edit in edits {
+            match *edit {
+                Edit::Move { page, from, to } => {
+                    assert_ne!(from, to, "no-op move of {}", page);
+                    assert_eq!(current.remove(from), page);
+                    current.insert(to, page);
+                }
+                Edit::Swap { pos, left, right } => {
+                    assert_eq!((current[pos], current[pos + 1]), (left, right));
+                    current.swap(pos, pos + 1);
+                }
+            }
+        }
+        current
+    }
+
+    fn is_valid(rules: &[(i32, i32)], order: &[i32]) -> bool {
+        let pos = |page: i32| order.iter().position(|&p| p == page).unwrap();
+        rules.iter().all(|&(src, dest)| pos(src) < pos(dest))
+    }
+
+    fn inversions(seq: &[i32], order: &[i32]) -> usize {
+        let pos = |page: i32| order.iter().position(|&p| p == page).unwrap();
+        let mut count = 0;
+        for i in 0..seq.len() {
+            for j in i + 1..seq.len() {
+                if pos(seq[i]) > pos(seq[j]) {
+                    count += 1;
+                }
+            }
+        }
+        count
+    }
+
+    fn permutations(pages: &[i32]) -> Vec<Vec<i32>> {
+        if pages.len() <= 1 {
+            return vec![pages.to_vec()];
+        }
+        let mut all = Vec::new();
+        for i in 0..pages.len() {
+            let mut rest = pages.to_vec();
+            let first = rest.remove(i);
+            for mut perm in permutations(&rest) {
+                perm.insert(0, first);
+                all.push(perm);
+            }
+        }
+        all
+    }
+
+    /// Checks both scripts replay to their order, the orders are valid, and
+    /// their lengths match the best any valid order can do.
+    fn check(rules: &[(i32, i32)], seq: &[i32]) {
+        let g = subgraph(rules, seq);
+        let valid: Vec<Vec<i32>> = permutations(seq)
+            .into_iter()
+            .filter(|p| is_valid(rules, p))
+            .collect();
+
+        let moves = correct(&g, seq, Correction::Moves, TieBreak::Smallest).unwrap();
+        assert!(is_valid(rules, &moves.order));
+        assert_eq!(replay(seq, &moves.edits), moves.order);
+        let fewest_moves = valid
+            .iter()
+            .map(|p| seq.len() - common_subsequence(seq, p).len())
+            .min()
+            .unwrap();
+        assert_eq!(moves.edits.len(), fewest_moves, "moves for {:?}", seq);
+
+        let swaps = correct(&g, seq, Correction::Swaps, TieBreak::Smallest).unwrap();
+        assert!(is_valid(rules, &swaps.order));
+        assert_eq!(replay(seq, &swaps.edits), swaps.order);
+        let fewest_swaps = valid.iter().map(|p| inversions(seq, p)).min().unwrap();
+        assert_eq!(swaps.edits.len(), fewest_swaps, "swaps for {:?}", seq);
+    }
+
+    #[test]
+    fn sample_update() {
+        let rules = [
+            (97, 13),
+            (97, 47),
+            (75, 29),
+            (29, 13),
+            (97, 29),
+            (47, 13),
+            (75, 47),
+            (97, 75),
+            (47, 29),
+            (75, 13),
+        ];
+        let seq = [97, 13, 75, 29, 47];
+        let g = subgraph(&rules, &seq);
+        let moves = correct(&g, &seq, Correction::Moves, TieBreak::Smallest).unwrap();
+        assert_eq!(moves.order, vec![97, 75, 47, 29, 13]);
+        assert_eq!(replay(&seq, &moves.edits), moves.order);
+        check(&rules, &seq);
+    }
+
+    #[test]
+    fn several_valid_orders() {
+        let rules = [(10, 11), (11, 13)];
+        let seq = [11, 13, 12, 10];
+        let g = subgraph(&rules, &seq);
+        let swaps = correct(&g, &seq, Correction::Swaps, TieBreak::Smallest).unwrap();
+        assert!(!swaps.unique);
+        assert_eq!(swaps.order, vec![10, 11, 13, 12]);
+        assert_eq!(swaps.edits.len(), 3);
+        check(&rules, &seq);
+    }
+
+    #[test]
+    fn random_sparse_rules() {
+        // Small LCG so the cases are the same on every run.
+        let mut state: u64 = 42;
+        let mut next = move |bound: u64| {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) % bound
+        };
+        for _ in 0..200 {
+            let mut seq: Vec<i32> = (11..=16).collect();
+            for i in (1..seq.len()).rev() {
+                seq.swap(i, next(i as u64 + 1) as usize);
+            }
+            // Rules only go from a smaller to a larger page, so no cycles.
+            let mut rules = Vec::new();
+            for a in 11..=16 {
+                for b in a + 1..=16 {
+                    if next(4) == 0 {
+                        rules.push((a, b));
+                    }
+                }
+            }
+            check(&rules, &seq);
+        }
+    }
+}
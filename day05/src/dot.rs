@@ -0,0 +1,71 @@
+use crate::edge_set::EdgeSet;
+use crate::validation::Violation;
+use std::fmt::Write;
+
+/// An update drawn on top of its subgraph: the rules it breaks are drawn in
+/// red and every page is labelled with where it moved to in `corrected`.
+pub struct UpdateView<'a> {
+    pub seq: &'a [i32],
+    pub violations: &'a [Violation],
+    pub corrected: Option<&'a [i32]>,
+}
+
+/// Writes the graph in Graphviz DOT format. Nodes and edges are sorted so the
+/// output is stable between runs.
+pub fn to_dot(g: &EdgeSet, name: &str, view: Option<&UpdateView>) -> String {
+    let mut out = String::new();
+    writeln!(out, "digraph \"{}\" {{", name).unwrap();
+    writeln!(out, "    rankdir=LR;").unwrap();
+    if let Some(corrected) = view.and_then(|v| v.corrected) {
+        writeln!(out, "    label=\"corrected: {}\";", join(corrected)).unwrap();
+        writeln!(out, "    labelloc=t;").unwrap();
+    }
+
+    for node in g.nodes() {
+        match view {
+            Some(view) => {
+                let from = view.seq.iter().position(|&n| n == node);
+                let to = view
+                    .corrected
+                    .and_then(|c| c.iter().position(|&n| n == node));
+                let label = match (from, to) {
+                    (Some(from), Some(to)) if from != to => {
+                        format!("{}\\n{} -> {}", node, from, to)
+                    }
+                    (Some(from), _) => format!("{}\\n{}", node, from),
+                    _ => node.to_string(),
+                };
+                let moved = matches!((from, to), (Some(from), Some(to)) if from != to);
+                let style = if moved { ", style=bold" } else { "" };
+                writeln!(out, "    {} [label=\"{}\"{}];", node, label, style).unwrap();
+            }
+            None => writeln!(out, "    {};", node).unwrap(),
+        }
+    }
+
+    for src in g.nodes() {
+        let mut dests: Vec<i32> = g.neighbors(src).collect();
+        dests.sort();
+        for dest in dests {
+            let violated = view.is_some_and(|v| {
+                v.violations
+                    .iter()
+                    .any(|violation| violation.before == src && violation.after == dest)
+            });
+            if violated {
+                writeln!(out, "    {} -> {} [color=red, penwidth=2];", src, dest).unwrap();
+            } else {
+                writeln!(out, "    {} -> {};", src, dest).unwrap();
+            }
+        }
+    }
+    writeln!(out, "}}").unwrap();
+    out
+}
+
+fn join(seq: &[i32]) -> String {
+    seq.iter()
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
@@ -1,14 +1,37 @@
+use crate::closure::{self, Closure};
+use crate::topo_sort::{self, Cycle, TieBreak};
 use std::collections::HashMap;
 use std::collections::HashSet;
 
+/// A rule that was added to or removed from an `EdgeSet`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RuleChange {
+    Added(i32, i32),
+    Removed(i32, i32),
+}
+
+impl RuleChange {
+    pub fn rule(&self) -> (i32, i32) {
+        match *self {
+            RuleChange::Added(src, dest) | RuleChange::Removed(src, dest) => (src, dest),
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct EdgeSet {
-    pub edges: HashMap<i32, HashSet<i32>>
+    edges: HashMap<i32, HashSet<i32>>,
+    /// Rule changes since the last call to `take_changes`, once
+    /// `record_changes` has been called. Every change goes through
+    /// `add_edge` or `remove_edge`, so none can be missed.
+    changes: Option<Vec<RuleChange>>,
 }
 
 impl EdgeSet {
     pub fn new() -> Self {
-        return EdgeSet {
-            edges: HashMap::new()
+        EdgeSet {
+            edges: HashMap::new(),
+            changes: None,
         }
     }
 
@@ -17,33 +40,100 @@ impl EdgeSet {
         for edge in edges {
             edge_set.add_edge(edge[0], edge[1]);
         }
-        return edge_set;
+        edge_set
     }
 
     pub fn has_edge(&self, src: i32, dest: i32) -> bool {
         match self.edges.get(&src) {
-            Some(edges) => {
-                if edges.contains(&dest) {
-                    return true;
-                }
-            },
-            None => return false,
+            Some(edges) => edges.contains(&dest),
+            None => false,
         }
-        return false;
+    }
+
+    /// Adds a page with no rules. Adding a page that is already there does
+    /// nothing; this is not a rule change.
+    pub fn add_node(&mut self, node: i32) {
+        self.edges.entry(node).or_default();
     }
 
     pub fn add_edge(&mut self, src: i32, dest: i32) {
-        match self.edges.get(&src) {
-            Some(edges) => {
-                if !edges.contains(&dest) {
-                    self.edges.get_mut(&src).unwrap().insert(dest);
+        if self.edges.entry(src).or_default().insert(dest) {
+            self.log(RuleChange::Added(src, dest));
+        }
+    }
+
+    /// Removes the rule `src|dest`. The pages stay in the set even if no
+    /// other rule mentions them. Returns whether the rule was there.
+    pub fn remove_edge(&mut self, src: i32, dest: i32) -> bool {
+        let removed = self
+            .edges
+            .get_mut(&src)
+            .is_some_and(|dests| dests.remove(&dest));
+        if removed {
+            self.log(RuleChange::Removed(src, dest));
+        }
+        removed
+    }
+
+    /// Starts logging rule changes for `take_changes`. Sets that are built
+    /// and thrown away, like subgraphs, never turn this on.
+    pub fn record_changes(&mut self) {
+        self.changes.get_or_insert_with(Vec::new);
+    }
+
+    /// Hands over the changes made since the last call, oldest first. Empty
+    /// unless `record_changes` was called.
+    pub fn take_changes(&mut self) -> Vec<RuleChange> {
+        self.changes
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
+    fn log(&mut self, change: RuleChange) {
+        if let Some(changes) = self.changes.as_mut() {
+            changes.push(change);
+        }
+    }
+
+    pub fn rule_count(&self) -> usize {
+        self.edges.values().map(|dests| dests.len()).sum()
+    }
+
+    /// Every page that appears in a rule, as a source or a destination, or
+    /// was added with `add_node`, in ascending order.
+    pub fn nodes(&self) -> Vec<i32> {
+        let mut nodes: HashSet<i32> = self.edges.keys().copied().collect();
+        for dests in self.edges.values() {
+            nodes.extend(dests);
+        }
+        let mut nodes: Vec<i32> = nodes.into_iter().collect();
+        nodes.sort();
+        nodes
+    }
+
+    pub fn neighbors(&self, src: i32) -> impl Iterator<Item = i32> + '_ {
+        self.edges.get(&src).into_iter().flatten().copied()
+    }
+
+    pub fn transitive_closure(&self) -> Closure {
+        Closure::new(self)
+    }
+
+    /// The smallest rule set with the same closure. Only well defined when the
+    /// rules have no cycle, otherwise the cycle is returned.
+    pub fn transitive_reduction(&self) -> Result<EdgeSet, Cycle> {
+        topo_sort::topo_sort(self, TieBreak::Smallest, &[])?;
+        let closure = self.transitive_closure();
+        let mut reduced = EdgeSet::new();
+        for src in self.nodes() {
+            reduced.add_node(src);
+            for dest in self.neighbors(src) {
+                if !closure::is_implied(&closure, src, dest) {
+                    reduced.add_edge(src, dest);
                 }
             }
-            None => {
-                let mut new_set = HashSet::new();
-                new_set.insert(dest);
-                self.edges.insert(src, new_set);
-            }
         }
+        Ok(reduced)
     }
-}
\ No newline at end of file
+}
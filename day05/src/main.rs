@@ -1,17 +1,72 @@
+mod closure;
+mod correction;
+mod dot;
 mod edge_set;
+mod queue_file;
+mod topo_sort;
+mod update_index;
+mod validation;
+use closure::Closure;
+use correction::Correction;
 use edge_set::EdgeSet;
-use std::collections::HashSet;
 use std::env::args;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
-use regex::Regex;
+use topo_sort::TieBreak;
+use update_index::UpdateIndex;
+use validation::Validation;
 
 fn main() {
     let mut file_name: String = String::new();
+    let mut tie_break = TieBreak::Smallest;
+    let mut validation = Validation::Chain;
+    let mut show_violations = false;
+    let mut dot_file: Option<String> = None;
+    let mut dot_dir: Option<String> = None;
+    let mut rule_report = false;
+    let mut correction = Correction::Topo;
+    let mut show_edits = false;
+    let mut changes_file: Option<String> = None;
+    let mut write_file: Option<String> = None;
     let args: Vec<String> = args().collect();
-    if args.len() > 1 {
-        file_name = args[1].clone();
-    } else {
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--tie-break" => {
+                let policy = iter.next().expect("--tie-break needs a policy");
+                tie_break = TieBreak::parse(policy).unwrap_or_else(|err| panic!("{}", err));
+            }
+            "--validation" => {
+                let mode = iter.next().expect("--validation needs a mode");
+                validation = Validation::parse(mode).unwrap_or_else(|err| panic!("{}", err));
+            }
+            "--show-violations" => show_violations = true,
+            "--rule-report" => rule_report = true,
+            "--correction" => {
+                let mode = iter.next().expect("--correction needs a mode");
+                correction = Correction::parse(mode).unwrap_or_else(|err| panic!("{}", err));
+            }
+            "--show-edits" => show_edits = true,
+            "--write" => write_file = Some(iter.next().expect("--write needs a file name").clone()),
+            "--rule-changes" => {
+                changes_file = Some(
+                    iter.next()
+                        .expect("--rule-changes needs a file name")
+                        .clone(),
+                )
+            }
+            "--dot" => dot_file = Some(iter.next().expect("--dot needs a file name").clone()),
+            "--dot-updates" => {
+                dot_dir = Some(
+                    iter.next()
+                        .expect("--dot-updates needs a directory")
+                        .clone(),
+                )
+            }
+            _ => file_name = arg.clone(),
+        }
+    }
+    if file_name.is_empty() {
         print!("Enter file name: ");
         io::stdout().flush().expect("Failed to flush stdout");
 
@@ -21,71 +76,226 @@ fn main() {
         file_name = file_name.trim().to_string();
     }
 
-    let (edges, sequences) = read_from_file(&file_name).expect("Error reading from file");
+    let (edges, sequences, bad_lines) =
+        queue_file::read_from_file(&file_name).expect("Error reading from file");
+    for bad_line in &bad_lines {
+        eprintln!("warning: skipped {}", bad_line);
+    }
+
+    let mut g: EdgeSet = EdgeSet::new_from_edges(edges);
+    let mut closure = g.transitive_closure();
+    if rule_report {
+        print_rule_report(&g, &closure);
+    }
+    if validation == Validation::Implied
+        && !closure::rule_report(&g, &closure).contradictory.is_empty()
+    {
+        eprintln!(
+            "warning: rules contradict each other, implied validation will reject most updates"
+        );
+    }
+    if let Some(write_file) = &write_file {
+        fs::write(write_file, queue_file::serialize(&g, &sequences))
+            .expect("Error writing rules and updates");
+    }
+    if let Some(dot_file) = &dot_file {
+        fs::write(dot_file, dot::to_dot(&g, "rules", None)).expect("Error writing DOT file");
+    }
+    if let Some(dot_dir) = &dot_dir {
+        fs::create_dir_all(dot_dir).expect("Error creating DOT directory");
+    }
 
-    let g: EdgeSet = EdgeSet::new_from_edges(edges);
     let mut sum: i32 = 0;
     let mut corrected_sum: i32 = 0;
-    for seq in sequences {
-        if validate_sequence(&g, &seq) {
+    for (idx, seq) in sequences.iter().enumerate() {
+        if validate(&g, &closure, seq, validation) {
             /*
             If the sequence is valid, we add the middle element to the sum.
              */
-            sum += get_mid_seq_element(&seq);
+            sum += get_mid_seq_element(seq);
+            if let Some(dot_dir) = &dot_dir {
+                let view = dot::UpdateView {
+                    seq,
+                    violations: &[],
+                    corrected: None,
+                };
+                write_update_dot(dot_dir, idx, &build_subgraph(&g, seq), &view);
+            }
         } else {
-            /* 
+            /*
             If the sequence is invalid, we try to correct it by building a subgraph
             from the original graph that only contains the nodes in the sequence.
             We then perform a topological sort on the subgraph which will give us a
             valid sequence if one exists. We then validate the corrected sequence
             and add the middle element to the corrected sum if it is valid.
-            */ 
-            let subgraph = build_subgraph(&g, &seq);
-            let corrected_seq: Vec<i32> = topo_sort(&subgraph);
-            if validate_sequence(&g, &corrected_seq) {
-                corrected_sum += get_mid_seq_element(&corrected_seq);
+            */
+            if show_violations {
+                println!("Update {:?} breaks:", seq);
+                for violation in validation::find_violations(&g, seq) {
+                    println!("  {}", violation);
+                }
+            }
+            let subgraph = build_subgraph(&g, seq);
+            let sorted = correction::correct(&subgraph, seq, correction, tie_break);
+            if let Some(dot_dir) = &dot_dir {
+                let violations = validation::find_violations(&g, seq);
+                let view = dot::UpdateView {
+                    seq,
+                    violations: &violations,
+                    corrected: sorted.as_ref().ok().map(|s| s.order.as_slice()),
+                };
+                write_update_dot(dot_dir, idx, &subgraph, &view);
+            }
+            match sorted {
+                Ok(sorted) => {
+                    if !sorted.unique {
+                        eprintln!("warning: update {:?} has more than one valid order", seq);
+                    }
+                    if show_edits {
+                        println!("Update {:?} -> {:?}:", seq, sorted.order);
+                        for edit in &sorted.edits {
+                            println!("  {}", edit);
+                        }
+                    }
+                    if validate(&g, &closure, &sorted.order, validation) {
+                        corrected_sum += get_mid_seq_element(&sorted.order);
+                    }
+                }
+                Err(cycle) => {
+                    eprintln!(
+                        "warning: update {:?} can't be ordered, rules form a cycle: {}",
+                        seq, cycle
+                    );
+                }
             }
         }
     }
 
     println!("Total Sum: {}", sum);
     println!("Corrected Sum: {}", corrected_sum);
+
+    if let Some(changes_file) = &changes_file {
+        /*
+        Replay the rule changes one at a time. Only the updates containing both
+        pages of a changed rule are checked again, unless implied orderings are
+        on, since then one rule can reorder pages it doesn't mention.
+        */
+        let changes = read_rule_changes(changes_file).expect("Error reading rule changes");
+        g.record_changes();
+        let mut index = UpdateIndex::new(sequences, |seq| validate(&g, &closure, seq, validation));
+        for (add, src, dest) in changes {
+            if add {
+                g.add_edge(src, dest);
+            } else {
+                g.remove_edge(src, dest);
+            }
+            let applied = g.take_changes();
+            if applied.is_empty() {
+                println!("{}{}|{}: no change", if add { '+' } else { '-' }, src, dest);
+                continue;
+            }
+            let local = validation != Validation::Implied;
+            if !local {
+                closure = g.transitive_closure();
+            }
+            let flips = index.apply(&applied, local, |seq| {
+                validate(&g, &closure, seq, validation)
+            });
+            println!(
+                "{}{}|{}: {} update(s) flipped",
+                if add { '+' } else { '-' },
+                src,
+                dest,
+                flips.len()
+            );
+            for flip in flips {
+                println!(
+                    "  update {} {:?} is now {}",
+                    flip.update,
+                    index.update(flip.update),
+                    if flip.now_valid { "valid" } else { "invalid" }
+                );
+            }
+        }
+        let sum: i32 = (0..index.len())
+            .filter(|&idx| index.is_valid(idx))
+            .map(|idx| get_mid_seq_element(index.update(idx)))
+            .sum();
+        println!("Total Sum after changes: {}", sum);
+    }
 }
 
-fn read_from_file(file_name: &str) -> io::Result<(Vec<Vec<i32>>, Vec<Vec<i32>>)> {
+/// Reads lines like `+47|53` or `-47|53`, adding or removing a rule.
+fn read_rule_changes(file_name: &str) -> io::Result<Vec<(bool, i32, i32)>> {
     let file = File::open(file_name)?;
-    let lines = io::BufReader::new(file).lines();
+    let mut changes = Vec::new();
+    for (n, line) in io::BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let bad_line = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("line {}: expected +X|Y or -X|Y, got '{}'", n + 1, line),
+            )
+        };
+        let add = match line.chars().next() {
+            Some('+') => true,
+            Some('-') => false,
+            _ => return Err(bad_line()),
+        };
+        let (src, dest) = line[1..].split_once('|').ok_or_else(bad_line)?;
+        let src = src.trim().parse().map_err(|_| bad_line())?;
+        let dest = dest.trim().parse().map_err(|_| bad_line())?;
+        changes.push((add, src, dest));
+    }
+    Ok(changes)
+}
 
-    let mut graph_edges: Vec<Vec<i32>> = Vec::new();
-    let mut sequences: Vec<Vec<i32>> = Vec::new();
+fn validate(g: &EdgeSet, closure: &Closure, seq: &[i32], validation: Validation) -> bool {
+    match validation {
+        Validation::Chain => validate_sequence(g, seq),
+        Validation::Rules => validation::find_violations(g, seq).is_empty(),
+        Validation::Implied => validation::find_implied_violations(closure, seq).is_empty(),
+    }
+}
 
-    let re = Regex::new(r"^(\d+\|\d+)|(\d+(?:,\s?\d+)+)$").expect("Invalid regex");
-    for line in lines {
-        if let Some(cap) = re.captures(&line.unwrap()) {
-            if let Some(m) = cap.get(1) {
-                let s = m.as_str();
-                let v: Vec<i32> = parse_istring_to_vec(s, '|');
-                graph_edges.push(v);
-            } else if let Some(m) = cap.get(2) {
-                let s = m.as_str();
-                let v: Vec<i32> = parse_istring_to_vec(s, ',');
-                sequences.push(v);
-            }
+fn print_rule_report(g: &EdgeSet, closure: &Closure) {
+    let report = closure::rule_report(g, closure);
+    // Redundancy is judged with every cycle collapsed to one page, so the
+    // listed rules can all be dropped together.
+    println!("Redundant rules: {}", report.redundant.len());
+    if !report.contradictory.is_empty() {
+        println!("  (rules inside a cycle are never counted as redundant)");
+    }
+    for (a, b) in &report.redundant {
+        println!("  {}|{}", a, b);
+    }
+    println!("Contradictory pairs: {}", report.contradictory.len());
+    for (a, b) in &report.contradictory {
+        println!("  {} and {}", a, b);
+    }
+    match g.transitive_reduction() {
+        Ok(reduced) => {
+            println!("Rules after reduction: {}", reduced.rule_count());
         }
+        Err(cycle) => println!("No reduction, rules form a cycle: {}", cycle),
     }
-
-    return Ok((graph_edges, sequences));
 }
 
-fn parse_istring_to_vec(s: &str, del: char) -> Vec<i32> {
-    let v: Vec<i32> = s
-        .split(del)
-        .filter_map(|num| num.parse::<i32>().ok())
-        .collect();
-    return v;
+/// Writes the subgraph of update `idx` to `DIR/update_<idx>.dot`.
+fn write_update_dot(dot_dir: &str, idx: usize, subgraph: &EdgeSet, view: &dot::UpdateView) {
+    let name = format!("update_{}", idx);
+    fs::write(
+        format!("{}/{}.dot", dot_dir, name),
+        dot::to_dot(subgraph, &name, Some(view)),
+    )
+    .expect("Error writing DOT file");
 }
 
-fn validate_sequence(g: &EdgeSet, seq: &Vec<i32>) -> bool {
+fn validate_sequence(g: &EdgeSet, seq: &[i32]) -> bool {
     for i in seq.windows(2) {
         let src = i[0];
         let dest = i[1];
@@ -93,53 +303,27 @@ fn validate_sequence(g: &EdgeSet, seq: &Vec<i32>) -> bool {
             return false;
         }
     }
-    return true;
+    true
 }
 
-fn get_mid_seq_element(seq: &Vec<i32>) -> i32 {
+fn get_mid_seq_element(seq: &[i32]) -> i32 {
     let len = seq.len();
-    if len % 2 == 0 {
-        return seq[len / 2 - 1];
+    if len.is_multiple_of(2) {
+        seq[len / 2 - 1]
     } else {
-        return seq[len / 2];
+        seq[len / 2]
     }
 }
 
-fn build_subgraph(g: &EdgeSet, seq: &Vec<i32>) -> EdgeSet {
-    let mut subgraph= EdgeSet::new();
+fn build_subgraph(g: &EdgeSet, seq: &[i32]) -> EdgeSet {
+    let mut subgraph = EdgeSet::new();
     for &node in seq {
-        subgraph.edges.entry(node).or_insert(HashSet::new());
-        if let Some(edges) = g.edges.get(&node) {
-            for &neighbor in edges {
-                if seq.contains(&neighbor) {
-                    subgraph.add_edge(node, neighbor);
-                }
+        subgraph.add_node(node);
+        for neighbor in g.neighbors(node) {
+            if seq.contains(&neighbor) {
+                subgraph.add_edge(node, neighbor);
             }
         }
     }
-    return subgraph;
-}
-
-fn topo_sort(g: &EdgeSet) -> Vec<i32> {
-    let mut visited: HashSet<i32> = HashSet::new();
-    let mut stack: Vec<i32> = Vec::new();
-    for node in g.edges.keys() {
-        if !visited.contains(node) {
-            dfs(g, *node, &mut visited, &mut stack);
-        }
-    }
-    stack.reverse();
-    return stack;
+    subgraph
 }
-
-fn dfs(g: &EdgeSet, node: i32, visited: &mut HashSet<i32>, stack: &mut Vec<i32>) {
-    visited.insert(node);
-    if let Some(edges) = g.edges.get(&node) {
-        for &neighbor in edges {
-            if !visited.contains(&neighbor) {
-                dfs(g, neighbor, visited, stack);
-            }
-        }
-    }
-    stack.push(node);
-}
\ No newline at end of file
@@ -0,0 +1,111 @@
+use crate::edge_set::EdgeSet;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead};
+
+pub type Rules = Vec<Vec<i32>>;
+pub type Updates = Vec<Vec<i32>>;
+
+/// A line that is neither a rule in the rules section nor an update in the
+/// updates section. `line` is 1 based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct BadLine {
+    pub line: usize,
+    pub text: String,
+    pub reason: String,
+}
+
+impl fmt::Display for BadLine {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {} in '{}'", self.line, self.reason, self.text)
+    }
+}
+
+/// Reads the rules section, up to the first blank line, and the updates
+/// section after it. Lines that don't parse are returned instead of dropped;
+/// the rest of the file is still read.
+pub fn read_from_file(file_name: &str) -> io::Result<(Rules, Updates, Vec<BadLine>)> {
+    let file = File::open(file_name)?;
+    let lines = io::BufReader::new(file).lines();
+
+    let mut rules: Rules = Vec::new();
+    let mut updates: Updates = Vec::new();
+    let mut bad_lines: Vec<BadLine> = Vec::new();
+    let mut in_updates = false;
+
+    for (n, line) in lines.enumerate() {
+        let line = line?;
+        let text = line.trim();
+        if text.is_empty() {
+            // Only the first blank line after some rules ends the section;
+            // extra blank lines around the separator are ignored.
+            if !rules.is_empty() || !bad_lines.is_empty() {
+                in_updates = true;
+            }
+            continue;
+        }
+        let parsed = if in_updates {
+            parse_update(text).map(|u| updates.push(u))
+        } else {
+            parse_rule(text).map(|r| rules.push(r))
+        };
+        if let Err(reason) = parsed {
+            bad_lines.push(BadLine {
+                line: n + 1,
+                text: line.clone(),
+                reason,
+            });
+        }
+    }
+
+    Ok((rules, updates, bad_lines))
+}
+
+/// `X|Y`, with two page numbers.
+fn parse_rule(text: &str) -> Result<Vec<i32>, String> {
+    let Some((src, dest)) = text.split_once('|') else {
+        return Err(if text.contains(',') {
+            "update in the rules section, missing blank line?".to_string()
+        } else {
+            "expected a rule X|Y".to_string()
+        });
+    };
+    Ok(vec![parse_page(src)?, parse_page(dest)?])
+}
+
+/// `A,B,C`, with at least one page number.
+fn parse_update(text: &str) -> Result<Vec<i32>, String> {
+    if text.contains('|') {
+        return Err("rule in the updates section".to_string());
+    }
+    text.split(',').map(parse_page).collect()
+}
+
+fn parse_page(s: &str) -> Result<i32, String> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err("missing page number".to_string());
+    }
+    s.parse::<i32>()
+        .map_err(|_| format!("'{}' is not a page number", s))
+}
+
+/// Writes rules and updates back in the puzzle's format. Rules are sorted so
+/// the output doesn't depend on hash order.
+pub fn serialize(g: &EdgeSet, updates: &[Vec<i32>]) -> String {
+    let mut out = String::new();
+    for src in g.nodes() {
+        let mut dests: Vec<i32> = g.neighbors(src).collect();
+        dests.sort();
+        for dest in dests {
+            out.push_str(&format!("{}|{}\n", src, dest));
+        }
+    }
+    out.push('\n');
+    for update in updates {
+        let pages: Vec<String> = update.iter().map(|p| p.to_string()).collect();
+        out.push_str(&pages.join(","));
+        out.push('\n');
+    }
+    out
+}
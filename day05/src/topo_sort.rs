@@ -0,0 +1,122 @@
+use crate::edge_set::EdgeSet;
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+/// Which page goes first when several have no remaining predecessors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TieBreak {
+    Smallest,
+    Largest,
+    /// Keep the pages in the order they had in the update.
+    Original,
+}
+
+impl TieBreak {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "smallest" => Ok(TieBreak::Smallest),
+            "largest" => Ok(TieBreak::Largest),
+            "original" => Ok(TieBreak::Original),
+            _ => Err(format!("unknown tie-break policy '{}'", s)),
+        }
+    }
+}
+
+/// A valid ordering. `unique` is true when no tie ever had to be broken, i.e.
+/// the rules allow exactly this order and no other.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TopoOrder {
+    pub order: Vec<i32>,
+    pub unique: bool,
+}
+
+/// Pages that have to come before each other in a loop: each page must
+/// precede the next one and the last must precede the first.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Cycle(pub Vec<i32>);
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for node in &self.0 {
+            write!(f, "{} -> ", node)?;
+        }
+        write!(f, "{}", self.0[0])
+    }
+}
+
+/// Kahn's algorithm. `seq` is the original update, only used to rank pages
+/// for `TieBreak::Original`; pages missing from it go last.
+pub fn topo_sort(g: &EdgeSet, tie_break: TieBreak, seq: &[i32]) -> Result<TopoOrder, Cycle> {
+    let rank = |node: i32| -> i64 {
+        match tie_break {
+            TieBreak::Smallest => node as i64,
+            TieBreak::Largest => -(node as i64),
+            TieBreak::Original => seq
+                .iter()
+                .position(|&n| n == node)
+                .map_or(i64::MAX, |i| i as i64),
+        }
+    };
+
+    let nodes = g.nodes();
+    let mut in_degree: HashMap<i32, usize> = nodes.iter().map(|&n| (n, 0)).collect();
+    for &node in &nodes {
+        for dest in g.neighbors(node) {
+            *in_degree.get_mut(&dest).unwrap() += 1;
+        }
+    }
+
+    let mut ready: BTreeSet<(i64, i32)> = nodes
+        .iter()
+        .filter(|n| in_degree[n] == 0)
+        .map(|&n| (rank(n), n))
+        .collect();
+    let mut order = Vec::with_capacity(nodes.len());
+    let mut unique = true;
+    while let Some((_, node)) = ready.pop_first() {
+        if !ready.is_empty() {
+            unique = false;
+        }
+        order.push(node);
+        for dest in g.neighbors(node) {
+            let degree = in_degree.get_mut(&dest).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.insert((rank(dest), dest));
+            }
+        }
+    }
+
+    if order.len() < nodes.len() {
+        let remaining: Vec<i32> = nodes.into_iter().filter(|n| in_degree[n] > 0).collect();
+        return Err(find_cycle(g, &remaining));
+    }
+    Ok(TopoOrder { order, unique })
+}
+
+/// Every page Kahn's algorithm couldn't place still has a predecessor among
+/// the unplaced pages, so walking predecessors from any of them must
+/// eventually revisit a page. The walk from there on is a cycle.
+fn find_cycle(g: &EdgeSet, remaining: &[i32]) -> Cycle {
+    let mut predecessor: HashMap<i32, i32> = HashMap::new();
+    for &src in remaining {
+        for dest in g.neighbors(src) {
+            let pred = predecessor.entry(dest).or_insert(src);
+            *pred = (*pred).min(src);
+        }
+    }
+
+    let mut path: Vec<i32> = Vec::new();
+    let mut node = *remaining.iter().min().unwrap();
+    while !path.contains(&node) {
+        path.push(node);
+        node = predecessor[&node];
+    }
+    let start = path.iter().position(|&n| n == node).unwrap();
+    let mut cycle = path[start..].to_vec();
+    cycle.reverse();
+    // Rotate so the smallest page comes first, for a stable report.
+    let min_idx = (0..cycle.len()).min_by_key(|&i| cycle[i]).unwrap();
+    cycle.rotate_left(min_idx);
+    Cycle(cycle)
+}
@@ -0,0 +1,100 @@
+use crate::edge_set::RuleChange;
+use std::collections::HashMap;
+
+/// An update that became valid or invalid after a rule change.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Flip {
+    pub update: usize,
+    pub now_valid: bool,
+}
+
+/// Remembers which updates contain each page, so a changed rule `a|b` only
+/// has to re-check the updates that contain both `a` and `b`.
+pub struct UpdateIndex {
+    updates: Vec<Vec<i32>>,
+    by_page: HashMap<i32, Vec<usize>>,
+    valid: Vec<bool>,
+}
+
+impl UpdateIndex {
+    pub fn new(updates: Vec<Vec<i32>>, validate: impl Fn(&[i32]) -> bool) -> Self {
+        let mut by_page: HashMap<i32, Vec<usize>> = HashMap::new();
+        for (idx, update) in updates.iter().enumerate() {
+            for &page in update {
+                let list = by_page.entry(page).or_default();
+                if list.last() != Some(&idx) {
+                    list.push(idx);
+                }
+            }
+        }
+        let valid = updates.iter().map(|u| validate(u)).collect();
+        UpdateIndex {
+            updates,
+            by_page,
+            valid,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn update(&self, idx: usize) -> &[i32] {
+        &self.updates[idx]
+    }
+
+    pub fn is_valid(&self, idx: usize) -> bool {
+        self.valid[idx]
+    }
+
+    /// Indices of the updates that contain both pages of the rule, ascending.
+    pub fn affected_by(&self, src: i32, dest: i32) -> Vec<usize> {
+        let (Some(with_src), Some(with_dest)) = (self.by_page.get(&src), self.by_page.get(&dest))
+        else {
+            return Vec::new();
+        };
+        with_src
+            .iter()
+            .copied()
+            .filter(|idx| with_dest.binary_search(idx).is_ok())
+            .collect()
+    }
+
+    /// Re-checks the updates touched by `changes` with `validate`, which must
+    /// already see the changed rules, and returns the ones that flipped.
+    /// `local` is false when a rule can affect updates that don't contain its
+    /// pages, e.g. with implied orderings; then every update is re-checked.
+    pub fn apply(
+        &mut self,
+        changes: &[RuleChange],
+        local: bool,
+        validate: impl Fn(&[i32]) -> bool,
+    ) -> Vec<Flip> {
+        let mut affected: Vec<usize> = if local {
+            changes
+                .iter()
+                .flat_map(|change| {
+                    let (src, dest) = change.rule();
+                    self.affected_by(src, dest)
+                })
+                .collect()
+        } else {
+            (0..self.updates.len()).collect()
+        };
+        affected.sort();
+        affected.dedup();
+
+        let mut flips = Vec::new();
+        for idx in affected {
+            let now_valid = validate(&self.updates[idx]);
+            if now_valid != self.valid[idx] {
+                self.valid[idx] = now_valid;
+                flips.push(Flip {
+                    update: idx,
+                    now_valid,
+                });
+            }
+        }
+        flips
+    }
+}
@@ -0,0 +1,85 @@
+use crate::closure::Closure;
+use crate::edge_set::EdgeSet;
+use std::fmt;
+
+/// How an update is checked against the rules.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Validation {
+    /// Every adjacent pair of pages needs its own rule `X|Y`.
+    Chain,
+    /// No rule `X|Y` may have `Y` printed before `X`. Pages without a rule
+    /// between them can go in any order.
+    Rules,
+    /// Like `Rules`, but also counts orderings implied by chains of rules.
+    /// Only meaningful when the rule set has no contradictions.
+    Implied,
+}
+
+impl Validation {
+    pub fn parse(s: &str) -> Result<Self, String> {
+        match s {
+            "chain" => Ok(Validation::Chain),
+            "rules" => Ok(Validation::Rules),
+            "implied" => Ok(Validation::Implied),
+            _ => Err(format!("unknown validation mode '{}'", s)),
+        }
+    }
+}
+
+/// A rule `before|after` broken by an update, with the positions of both
+/// pages in the update.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Violation {
+    pub before: i32,
+    pub after: i32,
+    pub before_pos: usize,
+    pub after_pos: usize,
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}|{} ({} at position {}, {} at position {})",
+            self.before, self.after, self.before, self.before_pos, self.after, self.after_pos
+        )
+    }
+}
+
+/// Checks every pair of pages in `seq`, not just neighbours, and returns each
+/// rule the update breaks.
+pub fn find_violations(g: &EdgeSet, seq: &[i32]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (i, &earlier) in seq.iter().enumerate() {
+        for (j, &later) in seq.iter().enumerate().skip(i + 1) {
+            if g.has_edge(later, earlier) {
+                violations.push(Violation {
+                    before: later,
+                    after: earlier,
+                    before_pos: j,
+                    after_pos: i,
+                });
+            }
+        }
+    }
+    violations
+}
+
+/// Like `find_violations`, but a pair is out of order when any chain of rules
+/// says so. `before|after` in the result need not be a rule itself.
+pub fn find_implied_violations(closure: &Closure, seq: &[i32]) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    for (i, &earlier) in seq.iter().enumerate() {
+        for (j, &later) in seq.iter().enumerate().skip(i + 1) {
+            if closure.must_precede(later, earlier) {
+                violations.push(Violation {
+                    before: later,
+                    after: earlier,
+                    before_pos: j,
+                    after_pos: i,
+                });
+            }
+        }
+    }
+    violations
+}
@@ -0,0 +1,145 @@
+pub type Position = (i32, i32);
+
+/// The way a guard is facing.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Direction {
+    Up,
+    Right,
+    Down,
+    Left,
+}
+
+impl Direction {
+    pub const ALL: [Direction; 4] = [
+        Direction::Up,
+        Direction::Right,
+        Direction::Down,
+        Direction::Left,
+    ];
+
+    pub fn turn_right(self) -> Self {
+        match self {
+            Direction::Up => Direction::Right,
+            Direction::Right => Direction::Down,
+            Direction::Down => Direction::Left,
+            Direction::Left => Direction::Up,
+        }
+    }
+
+    /// `(row, col)` change of one step.
+    pub fn delta(self) -> (i32, i32) {
+        match self {
+            Direction::Up => (-1, 0),
+            Direction::Right => (0, 1),
+            Direction::Down => (1, 0),
+            Direction::Left => (0, -1),
+        }
+    }
+
+    pub fn is_vertical(self) -> bool {
+        matches!(self, Direction::Up | Direction::Down)
+    }
+
+    /// The guard symbol on the map.
+    pub fn facing(self) -> char {
+        match self {
+            Direction::Up => '^',
+            Direction::Right => '>',
+            Direction::Down => 'v',
+            Direction::Left => '<',
+        }
+    }
+
+    pub fn from_facing(ch: char) -> Option<Self> {
+        Direction::ALL.into_iter().find(|d| d.facing() == ch)
+    }
+
+    /// A distinct bit per direction, for remembering which ways a guard has
+    /// already faced on a cell.
+    pub fn bit(self) -> u8 {
+        1 << self as u8
+    }
+}
+
+/// What happens when a guard tries to move.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    /// It gets to this cell.
+    Move(Position),
+    /// It walks off the map.
+    Exit,
+    /// A wall is right in front of it.
+    Blocked,
+}
+
+/// The map as a dense row-major array of walls. The top left cell sits at
+/// `origin`, so positions can be negative.
+#[derive(Clone)]
+pub struct Grid {
+    pub origin: Position,
+    pub nrows: usize,
+    pub ncols: usize,
+    walls: Vec<bool>,
+}
+
+impl Grid {
+    pub fn new(map: &[Vec<char>], origin: Position) -> Self {
+        let nrows = map.len();
+        let ncols = map.first().map_or(0, |row| row.len());
+        let mut walls = vec![false; nrows * ncols];
+        for (r, row) in map.iter().enumerate() {
+            for (c, &ch) in row.iter().enumerate().take(ncols) {
+                walls[r * ncols + c] = ch == '#';
+            }
+        }
+        Grid {
+            origin,
+            nrows,
+            ncols,
+            walls,
+        }
+    }
+
+    pub fn in_bounds(&self, pos: Position) -> bool {
+        let (r, c) = (pos.0 - self.origin.0, pos.1 - self.origin.1);
+        r >= 0 && r < self.nrows as i32 && c >= 0 && c < self.ncols as i32
+    }
+
+    pub fn index(&self, pos: Position) -> usize {
+        (pos.0 - self.origin.0) as usize * self.ncols + (pos.1 - self.origin.1) as usize
+    }
+
+    /// Every position on the map, row by row.
+    pub fn positions(&self) -> impl Iterator<Item = Position> + '_ {
+        (0..self.nrows as i32).flat_map(move |r| {
+            (0..self.ncols as i32).map(move |c| (self.origin.0 + r, self.origin.1 + c))
+        })
+    }
+
+    pub fn is_wall(&self, pos: Position) -> bool {
+        self.walls[self.index(pos)]
+    }
+
+    /// How many steps from `pos` in direction `dir` until off the map.
+    pub fn steps_to_edge(&self, pos: Position, dir: Direction) -> i32 {
+        let (r, c) = (pos.0 - self.origin.0, pos.1 - self.origin.1);
+        match dir {
+            Direction::Up => r + 1,
+            Direction::Down => self.nrows as i32 - r,
+            Direction::Left => c + 1,
+            Direction::Right => self.ncols as i32 - c,
+        }
+    }
+
+    pub fn step(&self, pos: Position, dir: Direction) -> Transition {
+        let (dr, dc) = dir.delta();
+        let next = (pos.0 + dr, pos.1 + dc);
+        if !self.in_bounds(next) {
+            Transition::Exit
+        } else if self.is_wall(next) {
+            Transition::Blocked
+        } else {
+            Transition::Move(next)
+        }
+    }
+}
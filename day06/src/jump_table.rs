@@ -0,0 +1,41 @@
+use crate::grid::{Direction, Grid, Position, Transition};
+
+/// For every cell and direction, where a guard walking that way ends up:
+/// `Move` to the cell in front of the next wall, `Exit` if it walks off the
+/// map, or `Blocked` if it is already facing a wall.
+pub struct JumpTable {
+    grid: Grid,
+    jumps: Vec<Transition>,
+}
+
+impl JumpTable {
+    pub fn new(grid: &Grid) -> Self {
+        let mut jumps = vec![Transition::Exit; grid.nrows * grid.ncols * 4];
+        for dir in Direction::ALL {
+            let (dr, dc) = dir.delta();
+            // Visit cells so the neighbour in direction `dir` is always done
+            // first, then each cell continues from its neighbour's stop.
+            let mut positions: Vec<Position> = grid.positions().collect();
+            if dr > 0 || dc > 0 {
+                positions.reverse();
+            }
+            for pos in positions {
+                jumps[grid.index(pos) * 4 + dir as usize] = match grid.step(pos, dir) {
+                    Transition::Move(next) => match jumps[grid.index(next) * 4 + dir as usize] {
+                        Transition::Blocked => Transition::Move(next),
+                        further => further,
+                    },
+                    other => other,
+                };
+            }
+        }
+        JumpTable {
+            grid: grid.clone(),
+            jumps,
+        }
+    }
+
+    pub fn jump(&self, pos: Position, dir: Direction) -> Transition {
+        self.jumps[self.grid.index(pos) * 4 + dir as usize]
+    }
+}
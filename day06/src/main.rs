@@ -1,21 +1,49 @@
+mod grid;
+mod jump_table;
+mod overlay;
+mod render;
+
+use grid::{Direction, Grid, Position, Transition};
+use jump_table::JumpTable;
+use overlay::Overlay;
+use rayon::prelude::*;
+use render::State;
+use std::collections::HashSet;
 use std::env::args;
-use std::fs::File;
+use std::fs::{self, File};
 use std::io::{self, BufRead, Write};
-use std::collections::{HashSet, HashMap};
 
-const DIRECTIONS: [(i32, i32); 4] = [(-1, 0), (0, 1), (1, 0), (0, -1)];
-
-type Position = (i32, i32);
-type DirectionMap = HashMap<usize, Position>;
-type Graph = HashMap<Position, DirectionMap>;
+type Map = Vec<Vec<char>>;
 
 fn main() {
     // Read the file name from the command line arguments or prompt the user for the file name
     let mut file_name: String = String::new();
+    let mut render = false;
+    let mut svg_file: Option<String> = None;
+    let mut frames_dir: Option<String> = None;
+    let mut origin: Position = (0, 0);
     let args: Vec<String> = args().collect();
-    if args.len() > 1 {
-        file_name = args[1].clone();
-    } else {
+    let mut iter = args[1..].iter();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--render" => render = true,
+            "--svg" => svg_file = Some(iter.next().expect("--svg needs a file name").clone()),
+            "--frames" => {
+                frames_dir = Some(iter.next().expect("--frames needs a directory").clone())
+            }
+            "--origin" => {
+                // `ROW,COL` of the top left cell, e.g. `-5,-5` to centre the map
+                let spec = iter.next().expect("--origin needs ROW,COL");
+                let (r, c) = spec.split_once(',').expect("--origin expects ROW,COL");
+                origin = (
+                    r.trim().parse().expect("Invalid origin row"),
+                    c.trim().parse().expect("Invalid origin column"),
+                );
+            }
+            _ => file_name = arg.clone(),
+        }
+    }
+    if file_name.is_empty() {
         print!("Enter file name: ");
         io::stdout().flush().expect("Failed to flush stdout");
 
@@ -25,167 +53,159 @@ fn main() {
         file_name = file_name.trim().to_string();
     }
 
-    // Read the map and starting position from the file
-    let (map, start_pos): (Vec<Vec<char>>, (i32, i32)) = read_from_file(&file_name)
-        .expect("Error reading from file");
-
-    let graph = build_graph(&map);
-    let start_state = (start_pos.0, start_pos.1);
-    let path = get_base_path(&graph, start_state, 0);
-    let steps = path
-        .iter()
-        .map(|(r, c)| (r, c))
-        .collect::<HashSet<_>>().len();
-
-    println!("Number of steps: {}", steps);
-    let num_loops = try_obstacles(&graph, start_state, 0, &path);
-    println!("Number of loops: {}", num_loops);
+    // Read the map and the guards' starting states from the file
+    let (map, guards): (Map, Vec<State>) =
+        read_from_file(&file_name, origin).expect("Error reading from file");
+    if guards.is_empty() {
+        panic!("No guard (^, >, v or <) on the map");
+    }
+
+    /*
+    Guards don't see each other, so each one is simulated on its own and
+    only the results are combined.
+    */
+    let grid = Grid::new(&map, origin);
+    let table = JumpTable::new(&grid);
+    let mut all_visited: HashSet<Position> = HashSet::new();
+    let mut all_walls: HashSet<Position> = HashSet::new();
+    let mut paths: Vec<Vec<State>> = Vec::new();
+    for (i, &(start, start_dir)) in guards.iter().enumerate() {
+        let path = get_base_path(&Overlay::new(&grid, &table, &[]), start, start_dir);
+        let visited: HashSet<Position> = path.iter().map(|&(pos, _)| pos).collect();
+        let walls = try_obstacles(&grid, &table, start, &path);
+        if guards.len() > 1 {
+            println!(
+                "Guard {} at {:?} facing {}: {} steps, {} loops",
+                i + 1,
+                start,
+                start_dir.facing(),
+                visited.len(),
+                walls.len()
+            );
+        }
+        all_visited.extend(visited);
+        all_walls.extend(walls);
+        paths.push(path);
+    }
+
+    println!("Number of steps: {}", all_visited.len());
+    println!("Number of loops: {}", all_walls.len());
+
+    let paths: Vec<&[State]> = paths.iter().map(|p| p.as_slice()).collect();
+    let mut walls: Vec<Position> = all_walls.into_iter().collect();
+    walls.sort();
+    if render {
+        print!(
+            "{}",
+            render::to_text(&render::draw_route(&map, origin, &paths, &walls))
+        );
+    }
+    if let Some(svg_file) = &svg_file {
+        fs::write(svg_file, render::to_svg(&map, origin, &paths, &walls))
+            .expect("Error writing SVG");
+    }
+    if let Some(frames_dir) = &frames_dir {
+        fs::create_dir_all(frames_dir).expect("Error creating frames directory");
+        for (k, frame) in render::frames(&map, origin, &paths).iter().enumerate() {
+            fs::write(format!("{}/frame_{:05}.txt", frames_dir, k), frame)
+                .expect("Error writing frame");
+        }
+    }
 }
 
-fn read_from_file(file_name: &str) -> io::Result<(Vec<Vec<char>>, (i32, i32))> {
+/// Guard positions are offset so the top left cell is at `origin`.
+fn read_from_file(file_name: &str, origin: Position) -> io::Result<(Map, Vec<State>)> {
     let file = File::open(file_name)?;
     let lines = io::BufReader::new(file).lines();
 
-    let mut start_pos = (0, 0);
+    let mut guards: Vec<State> = Vec::new();
     let mut map: Vec<Vec<char>> = Vec::new();
     for line in lines {
         let line = line?;
         let row: Vec<char> = line.chars().collect();
-        if row.contains(&'^') {
-            for (i, &c) in row.iter().enumerate() {
-                if c == '^' {
-                    start_pos = (map.len() as i32, i as i32);
-                    break;
-                }
+        for (i, &c) in row.iter().enumerate() {
+            if let Some(dir) = Direction::from_facing(c) {
+                guards.push(((origin.0 + map.len() as i32, origin.1 + i as i32), dir));
             }
         }
         map.push(row);
     }
 
-    Ok((map, start_pos))
+    Ok((map, guards))
 }
 
-fn build_graph(map: &Vec<Vec<char>>) -> Graph {
-    let nrows = map.len();
-    let ncols = map[0].len();
-    let mut graph: Graph = HashMap::new();
-
-    for r in 0..nrows {
-        for c in 0..ncols {
-            if map[r][c] == '#' {
-                continue;
-            }
-            let mut dir_map: DirectionMap = HashMap::new();
-        
-            for d in 0..4 {
-                let (dr, dc) = DIRECTIONS[d];
-                let nr = r as i32 + dr;
-                let nc = c as i32 + dc;
-
-                if nr < 0 || nr >= nrows as i32 || nc < 0 || nc >= ncols as i32 {
-                    // Out of bounds, connect node to (nrows+1, ncols+1) to indicate out of bounds:
-                    dir_map.insert(d, (-1, -1));
-                } else if map[nr as usize][nc as usize] != '#' {
-                    // Direction is clear, add edge
-                    dir_map.insert(d,(nr, nc));
-                }
-            }
-            if !dir_map.is_empty() {
-                graph.insert((r as i32, c as i32), dir_map);
-            }
+/// Every cell the guard stands on, in order, with the direction it was
+/// walking when it got there. If the guard never leaves the map the path
+/// stops the first time it repeats a cell and direction.
+fn get_base_path(map: &Overlay, start: Position, start_dir: Direction) -> Vec<State> {
+    let grid = map.grid();
+    let mut seen: Vec<u8> = vec![0; grid.nrows * grid.ncols];
+    let mut path: Vec<State> = vec![(start, start_dir)];
+    let mut cur: Position = start;
+    let mut dir: Direction = start_dir;
+    loop {
+        let idx = grid.index(cur);
+        if seen[idx] & dir.bit() != 0 {
+            return path;
         }
-    }
-    return graph;
-}
-
-fn get_base_path(graph: &Graph, start: Position, start_dir: usize) -> Vec<Position> {
-    let mut path: Vec<(i32, i32)> = vec![start];
-    let mut cur: (i32, i32) = start;
-    let mut dir: usize = start_dir;
-    while let Some(edges) = graph.get(&cur) {
-        let next_node = edges.get(&dir);
-        match next_node {
-            Some(&next_node) => {
-                if next_node == (-1, -1) {
-                    return path
-                }
-                path.push(next_node);
-                cur = next_node;
-            }
-            None => {
-                dir = (dir + 1) % 4;
+        seen[idx] |= dir.bit();
+        match map.step(cur, dir) {
+            Transition::Exit => return path,
+            Transition::Blocked => dir = dir.turn_right(),
+            Transition::Move(next) => {
+                path.push((next, dir));
+                cur = next;
             }
         }
     }
-    return path;
 }
 
-fn check_loop(graph: &Graph, start: Position, start_dir: usize) -> bool {
-    let mut visited: HashMap<Position, Vec<usize>> = HashMap::new();
+/// Follows the guard from wall to wall. It loops exactly when it stops in
+/// front of the same wall facing the same way twice, so the work is one
+/// lookup per turn rather than per step.
+fn check_loop(map: &Overlay, start: Position, start_dir: Direction) -> bool {
+    let grid = map.grid();
+    let mut seen: Vec<u8> = vec![0; grid.nrows * grid.ncols];
     let mut cur: Position = start;
-    let mut dir: usize = start_dir;
-    while let Some(edges) = graph.get(&cur) {
-        let next_node = edges.get(&dir);
-        match next_node {
-            Some(&next_node) => {
-                if next_node == (-1, -1) {
-                    return false
-                }
-                if visited.contains_key(&next_node) &&
-                    visited.get(&next_node).unwrap().contains(&dir)
-                    {
-                    return true;
-                }
-                visited.entry(next_node).or_insert(Vec::new()).push(dir);
-                cur = next_node;
-            }
-            None => {
-                dir = (dir + 1) % 4;
-            }
+    let mut dir: Direction = start_dir;
+    loop {
+        let stop = match map.jump(cur, dir) {
+            Transition::Exit => return false,
+            Transition::Blocked => cur,
+            Transition::Move(stop) => stop,
+        };
+        let idx = grid.index(stop);
+        if seen[idx] & dir.bit() != 0 {
+            return true;
         }
+        seen[idx] |= dir.bit();
+        cur = stop;
+        dir = dir.turn_right();
     }
-    return false;
 }
 
-fn make_wall(graph: &Graph, position: Position) -> Graph {
-    let mut new_graph = graph.clone();
-
-    let affected_states: Vec<Position> = new_graph
-        .iter()
-        .filter_map(|(state, edges)| {
-            if edges.values().any(|&(r, c)| r == position.0 && c == position.1) {
-                Some(*state)
-            } else {
-                None
-            }
-        })
+/// Returns the cells where a wall would trap the guard in a loop, trying
+/// every cell of its path except the start. Until the guard first reaches
+/// the wall's cell the route is the same as without it, so each check
+/// resumes from the step before that instead of from the start. Every
+/// candidate shares the one grid and jump table and only adds
+/// its own wall through an overlay, so they can be checked in parallel.
+fn try_obstacles(grid: &Grid, table: &JumpTable, start: Position, path: &[State]) -> Vec<Position> {
+    let mut tried: HashSet<Position> = HashSet::new();
+    let candidates: Vec<(Position, State)> = path
+        .windows(2)
+        .filter(|w| w[1].0 != start && tried.insert(w[1].0))
+        .map(|w| (w[1].0, (w[0].0, w[1].1)))
         .collect();
 
-    for &st in &affected_states {
-        if let Some(edges) = new_graph.get_mut(&st) {
-            edges.retain(|&_, &mut (r, c)| r != position.0 || c != position.1);
-        } 
-    }
-    return new_graph;
-}
-
-fn try_obstacles(graph: &Graph, start: Position, start_dir: usize, path: &Vec<Position>) -> i32 {
-    let mut new_walls: Vec<Position> = Vec::new();
-    let mut num_loops = 0;
-    for &pos in path[2..].iter() {
-        if pos.0 == -1 && pos.1 == -1 {
-            continue;
-        }
-        
-        let mut new_graph = graph.clone();
-        new_graph = make_wall(&new_graph, pos);
-        if check_loop(&new_graph, start, start_dir) && 
-            !new_walls.contains(&pos) {
-            new_walls.push(pos);
-            num_loops += 1;
-        }
-    }
-    return num_loops;
+    candidates
+        .par_iter()
+        .filter(|&&(wall, (resume_pos, resume_dir))| {
+            let extra = [wall];
+            check_loop(&Overlay::new(grid, table, &extra), resume_pos, resume_dir)
+        })
+        .map(|&(wall, _)| wall)
+        .collect()
 }
 
-// num loops: 1623 < x < 1902
\ No newline at end of file
+// num loops: 1623 < x < 1902
@@ -0,0 +1,56 @@
+use crate::grid::{Direction, Grid, Position, Transition};
+use crate::jump_table::JumpTable;
+
+/// A map with a few extra walls on top, answering the same queries as the
+/// grid and jump table underneath without copying either. Cheap enough to
+/// build one per candidate wall, and shareable across threads.
+pub struct Overlay<'a> {
+    grid: &'a Grid,
+    table: &'a JumpTable,
+    extra: &'a [Position],
+}
+
+impl<'a> Overlay<'a> {
+    pub fn new(grid: &'a Grid, table: &'a JumpTable, extra: &'a [Position]) -> Self {
+        Overlay { grid, table, extra }
+    }
+
+    pub fn grid(&self) -> &Grid {
+        self.grid
+    }
+
+    /// Like `Grid::step`, with the extra walls.
+    pub fn step(&self, pos: Position, dir: Direction) -> Transition {
+        match self.grid.step(pos, dir) {
+            Transition::Move(next) if self.extra.contains(&next) => Transition::Blocked,
+            other => other,
+        }
+    }
+
+    /// Like `JumpTable::jump`, cut short by the nearest extra wall between
+    /// `pos` and where the guard would have stopped.
+    pub fn jump(&self, pos: Position, dir: Direction) -> Transition {
+        let (dr, dc) = dir.delta();
+        // Distance to the first wall or off the map along the segment.
+        let reach = match self.table.jump(pos, dir) {
+            Transition::Blocked => return Transition::Blocked,
+            Transition::Move(stop) => (stop.0 - pos.0).abs() + (stop.1 - pos.1).abs() + 1,
+            Transition::Exit => self.grid.steps_to_edge(pos, dir),
+        };
+
+        let mut nearest: Option<i32> = None;
+        for &(r, c) in self.extra {
+            let (dist_r, dist_c) = (r - pos.0, c - pos.1);
+            let dist = dist_r * dr + dist_c * dc;
+            let on_line = dist_r * dc == dist_c * dr;
+            if on_line && dist > 0 && dist < reach && nearest.is_none_or(|n| dist < n) {
+                nearest = Some(dist);
+            }
+        }
+        match nearest {
+            Some(1) => Transition::Blocked,
+            Some(dist) => Transition::Move((pos.0 + dr * (dist - 1), pos.1 + dc * (dist - 1))),
+            None => self.table.jump(pos, dir),
+        }
+    }
+}
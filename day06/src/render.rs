@@ -0,0 +1,161 @@
+use crate::grid::{Direction, Position};
+use std::fmt::Write;
+
+/// A cell and the direction the guard faces there.
+pub type State = (Position, Direction);
+
+const CELL: usize = 10;
+
+/// The map with each route drawn the way the puzzle does: `|` and `-` where
+/// the guard walked up/down or left/right, `+` where it turned or crossed its
+/// own route, and `O` on every wall in `walls`. Guards keep their symbol on
+/// their start cell. The map's top left cell is at `origin`.
+pub fn draw_route(
+    map: &[Vec<char>],
+    origin: Position,
+    paths: &[&[State]],
+    walls: &[Position],
+) -> Vec<Vec<char>> {
+    let mut out: Vec<Vec<char>> = map.to_vec();
+    for path in paths {
+        for (i, &(pos, dir_in)) in path.iter().enumerate() {
+            let (r, c) = (pos.0 - origin.0, pos.1 - origin.1);
+            let dir_out = path.get(i + 1).map_or(dir_in, |&(_, d)| d);
+            let mark = if dir_in != dir_out {
+                '+'
+            } else if dir_in.is_vertical() {
+                '|'
+            } else {
+                '-'
+            };
+            let cell = &mut out[r as usize][c as usize];
+            *cell = match (*cell, mark) {
+                (ch, _) if Direction::from_facing(ch).is_some() => ch,
+                ('|', '-') | ('-', '|') | ('+', _) => '+',
+                _ => mark,
+            };
+        }
+    }
+    for &(r, c) in walls {
+        out[(r - origin.0) as usize][(c - origin.1) as usize] = 'O';
+    }
+    out
+}
+
+pub fn to_text(drawn: &[Vec<char>]) -> String {
+    let mut out = String::new();
+    for row in drawn {
+        out.extend(row.iter());
+        out.push('\n');
+    }
+    out
+}
+
+/// One text frame per step of the patrol. Frame `k` shows each guard's route
+/// so far and the guard itself, facing its current way, after `k` steps.
+pub fn frames(map: &[Vec<char>], origin: Position, paths: &[&[State]]) -> Vec<String> {
+    let mut base: Vec<Vec<char>> = map.to_vec();
+    for path in paths {
+        let ((r, c), _) = path[0];
+        base[(r - origin.0) as usize][(c - origin.1) as usize] = '.';
+    }
+
+    let steps = paths.iter().map(|p| p.len()).max().unwrap_or(0);
+    (0..steps)
+        .map(|k| {
+            let prefixes: Vec<&[State]> =
+                paths.iter().map(|p| &p[..(k + 1).min(p.len())]).collect();
+            let mut drawn = draw_route(&base, origin, &prefixes, &[]);
+            for prefix in &prefixes {
+                let &((r, c), dir) = prefix.last().unwrap();
+                drawn[(r - origin.0) as usize][(c - origin.1) as usize] = dir.facing();
+            }
+            to_text(&drawn)
+        })
+        .collect()
+}
+
+/// The map as an SVG image: walls filled in, each route as a line, and the
+/// walls in `walls` as circles.
+pub fn to_svg(
+    map: &[Vec<char>],
+    origin: Position,
+    paths: &[&[State]],
+    walls: &[Position],
+) -> String {
+    let nrows = map.len();
+    let ncols = map.first().map_or(0, |row| row.len());
+    let center = |(r, c): Position| {
+        let (r, c) = ((r - origin.0) as usize, (c - origin.1) as usize);
+        (c * CELL + CELL / 2, r * CELL + CELL / 2)
+    };
+
+    let mut out = String::new();
+    writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\">",
+        ncols * CELL,
+        nrows * CELL
+    )
+    .unwrap();
+    writeln!(
+        out,
+        "  <rect width=\"100%\" height=\"100%\" fill=\"white\"/>"
+    )
+    .unwrap();
+    for (r, row) in map.iter().enumerate() {
+        for (c, &ch) in row.iter().enumerate() {
+            if ch == '#' {
+                writeln!(
+                    out,
+                    "  <rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"black\"/>",
+                    c * CELL,
+                    r * CELL,
+                    CELL,
+                    CELL
+                )
+                .unwrap();
+            }
+        }
+    }
+    for path in paths {
+        let points: Vec<String> = path
+            .iter()
+            .map(|&(pos, _)| {
+                let (x, y) = center(pos);
+                format!("{},{}", x, y)
+            })
+            .collect();
+        writeln!(
+            out,
+            "  <polyline points=\"{}\" fill=\"none\" stroke=\"blue\" stroke-width=\"2\"/>",
+            points.join(" ")
+        )
+        .unwrap();
+        let ((r, c), dir) = path[0];
+        let (x, y) = center((r, c));
+        let (dr, dc) = dir.delta();
+        writeln!(
+            out,
+            "  <line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"green\" stroke-width=\"4\"/>",
+            x,
+            y,
+            x as i32 + dc * CELL as i32 / 2,
+            y as i32 + dr * CELL as i32 / 2
+        )
+        .unwrap();
+    }
+    for &pos in walls {
+        let (x, y) = center(pos);
+        writeln!(
+            out,
+            "  <circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"none\" stroke=\"red\" stroke-width=\"2\"/>",
+            x,
+            y,
+            CELL / 2 - 1
+        )
+        .unwrap();
+    }
+    writeln!(out, "</svg>").unwrap();
+    out
+}